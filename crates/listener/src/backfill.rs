@@ -0,0 +1,278 @@
+use anchor_client::solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+    rpc_response::{
+        Response, RpcConfirmedTransactionStatusWithSignature, RpcLogsResponse, RpcResponseContext,
+    },
+};
+use solana_transaction_status_client_types::{
+    EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding,
+};
+use tracing::warn;
+
+use crate::{FetchSignaturesSnafu, FetchTransactionSnafu, InvalidSignatureSnafu, Result};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BackfillConfig {
+    /// Replay the transactions missed while disconnected before resuming live delivery
+    #[serde(default = "BackfillConfig::default_enabled")]
+    pub enabled: bool,
+
+    /// Signatures requested per `getSignaturesForAddress` page, at most 1000
+    #[serde(default = "BackfillConfig::default_page_size")]
+    pub page_size: usize,
+
+    /// Most recent transactions replayed after a reconnect, `None` for no limit
+    #[serde(default = "BackfillConfig::default_max_transactions")]
+    pub max_transactions: Option<usize>,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            page_size: Self::default_page_size(),
+            max_transactions: Self::default_max_transactions(),
+        }
+    }
+}
+
+impl BackfillConfig {
+    #[inline]
+    #[must_use]
+    pub const fn default_enabled() -> bool {
+        true
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_page_size() -> usize {
+        1000
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_max_transactions() -> Option<usize> {
+        None
+    }
+}
+
+/// Last transaction delivered by the listener
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub signature: String,
+    pub slot: u64,
+}
+
+impl Cursor {
+    #[must_use]
+    pub fn new(logs: &Response<RpcLogsResponse>) -> Self {
+        Self { signature: logs.value.signature.clone(), slot: logs.context.slot }
+    }
+}
+
+/// Signatures of the `pump_amm` transactions that landed after `cursor`, oldest first
+///
+/// # Errors
+/// Returns an error if `getSignaturesForAddress` fails
+pub async fn missed_signatures(
+    client: &RpcClient,
+    cursor: &Cursor,
+    config: &BackfillConfig,
+) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+    let until = cursor.signature.parse::<Signature>().ok();
+    let mut missed: Vec<RpcConfirmedTransactionStatusWithSignature> = Vec::new();
+    let mut before = None;
+    loop {
+        let page = client
+            .get_signatures_for_address_with_config(
+                &pump_amm::ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(config.page_size),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await
+            .context(FetchSignaturesSnafu)?;
+        let page_len = page.len();
+
+        // `until` is exclusive, but it may never be reached if the cursor transaction was
+        // dropped, in which case the slot bounds the search
+        let mut reached_cursor = false;
+        for status in page {
+            if status.slot < cursor.slot || status.signature == cursor.signature {
+                reached_cursor = true;
+                break;
+            }
+            missed.push(status);
+        }
+
+        if let Some(max_transactions) = config.max_transactions {
+            if missed.len() >= max_transactions {
+                warn!("Backfill limited to the {max_transactions} most recent transactions");
+                missed.truncate(max_transactions);
+                break;
+            }
+        }
+        if reached_cursor || page_len < config.page_size {
+            break;
+        }
+        before = match missed.last() {
+            Some(status) => Some(
+                status
+                    .signature
+                    .parse()
+                    .context(InvalidSignatureSnafu { signature: status.signature.clone() })?,
+            ),
+            None => break,
+        };
+    }
+
+    missed.reverse();
+    Ok(missed)
+}
+
+/// Fetch a transaction and shape its log messages like a `logsSubscribe` notification
+///
+/// # Errors
+/// Returns an error if the signature is malformed or `getTransaction` fails
+pub async fn fetch_logs(
+    client: &RpcClient,
+    signature: &str,
+) -> Result<Option<Response<RpcLogsResponse>>> {
+    let parsed = signature.parse::<Signature>().context(InvalidSignatureSnafu { signature })?;
+    let transaction = client
+        .get_transaction_with_config(
+            &parsed,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+        .context(FetchTransactionSnafu { signature })?;
+    Ok(logs_response(signature, &transaction))
+}
+
+/// Shape the log messages of a `getTransaction` result like a `logsSubscribe` notification
+///
+/// Returns `None` if the transaction carries no status metadata or log messages.
+#[must_use]
+pub fn logs_response(
+    signature: &str,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Option<Response<RpcLogsResponse>> {
+    let meta = transaction.transaction.meta.as_ref()?;
+    let logs = meta.log_messages.as_ref().map(Clone::clone)?;
+    Some(Response {
+        context: RpcResponseContext { slot: transaction.slot, api_version: None },
+        value: RpcLogsResponse { signature: signature.to_string(), err: meta.err.clone(), logs },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_rpc_client::mock_sender::Mocks;
+
+    use super::*;
+
+    const CURSOR: &str =
+        "z91Tb2euJY61DK4x6MLm5bc34LkSse7x2Pb45mRvoAYimDMwMYNATD4Un7Q17iLgfv1z7WBBG6aMSqY2xhgLQvU";
+
+    fn status(signature: &str, slot: u64) -> serde_json::Value {
+        json!({
+            "signature": signature,
+            "slot": slot,
+            "err": null,
+            "memo": null,
+            "blockTime": null,
+            "confirmationStatus": "confirmed",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_missed_signatures_are_returned_oldest_first() {
+        let mocks = Mocks::from([(
+            RpcRequest::GetSignaturesForAddress,
+            json!([
+                status("newest", 102),
+                status("middle", 101),
+                status("oldest", 100),
+                status(CURSOR, 100),
+                status("already-processed", 99),
+            ]),
+        )]);
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let cursor = Cursor { signature: CURSOR.to_string(), slot: 100 };
+
+        let missed = missed_signatures(&client, &cursor, &BackfillConfig::default()).await.unwrap();
+
+        let signatures: Vec<_> = missed.iter().map(|status| status.signature.as_str()).collect();
+        assert_eq!(signatures, ["oldest", "middle", "newest"]);
+    }
+
+    #[tokio::test]
+    async fn test_missed_signatures_keeps_most_recent_when_limited() {
+        let mocks = Mocks::from([(
+            RpcRequest::GetSignaturesForAddress,
+            json!([status("newest", 102), status("middle", 101), status("oldest", 100)]),
+        )]);
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let cursor = Cursor { signature: CURSOR.to_string(), slot: 90 };
+        let config = BackfillConfig { max_transactions: Some(2), ..BackfillConfig::default() };
+
+        let missed = missed_signatures(&client, &cursor, &config).await.unwrap();
+
+        let signatures: Vec<_> = missed.iter().map(|status| status.signature.as_str()).collect();
+        assert_eq!(signatures, ["middle", "newest"]);
+    }
+
+    #[test]
+    fn test_logs_response_from_transaction() {
+        let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(json!({
+            "slot": 330_012_345,
+            "blockTime": 1_743_407_221,
+            "transaction": {
+                "signatures": [CURSOR],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 0,
+                    },
+                    "accountKeys": [],
+                    "recentBlockhash": "11111111111111111111111111111111",
+                    "instructions": [],
+                },
+            },
+            "meta": {
+                "err": { "InstructionError": [0, { "Custom": 6004 }] },
+                "status": { "Err": { "InstructionError": [0, { "Custom": 6004 }] } },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "logMessages": [
+                    "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA invoke [1]",
+                    "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA failed: custom program error: 0x1774",
+                ],
+            },
+        }))
+        .unwrap();
+
+        let logs = logs_response(CURSOR, &transaction).unwrap();
+
+        assert_eq!(logs.context.slot, 330_012_345);
+        assert_eq!(logs.value.signature, CURSOR);
+        assert!(logs.value.err.is_some());
+        assert_eq!(logs.value.logs.len(), 2);
+    }
+}
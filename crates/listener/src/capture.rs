@@ -0,0 +1,223 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use solana_client::rpc_response::{Response, RpcLogsResponse};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncWriteExt, BufWriter},
+};
+
+use crate::{DecodeCaptureSnafu, OpenCaptureSnafu, ReadReplayFileSnafu, Result, WriteCaptureSnafu};
+
+/// Line of a capture file, a notification as the listener received it
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Capture {
+    pub received_at: DateTime<Utc>,
+    pub response: Response<RpcLogsResponse>,
+}
+
+/// Appends every notification of the live stream to a capture file, one JSON line each
+#[derive(Debug)]
+pub struct Recorder {
+    path: PathBuf,
+    file: BufWriter<File>,
+    line: Vec<u8>,
+}
+
+impl Recorder {
+    /// Open `path` for appending, creating it and its parent directories if needed
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened
+    pub async fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).await.context(OpenCaptureSnafu { path })?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .context(OpenCaptureSnafu { path })?;
+        Ok(Self { path: path.to_path_buf(), file: BufWriter::new(file), line: Vec::new() })
+    }
+
+    /// Append `response`, stamped with the current time
+    ///
+    /// Every line is flushed, so that the capture survives the listener being killed.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written
+    pub async fn record(&mut self, response: &Response<RpcLogsResponse>) -> Result<()> {
+        self.line.clear();
+        serde_json::to_writer(&mut self.line, &CaptureRef { received_at: Utc::now(), response })
+            .map_err(io::Error::from)
+            .context(WriteCaptureSnafu { path: &self.path })?;
+        self.line.push(b'\n');
+        self.file.write_all(&self.line).await.context(WriteCaptureSnafu { path: &self.path })?;
+        self.file.flush().await.context(WriteCaptureSnafu { path: &self.path })
+    }
+}
+
+/// Borrowed [`Capture`], to record a response without cloning it
+#[derive(Serialize)]
+struct CaptureRef<'a> {
+    received_at: DateTime<Utc>,
+    response: &'a Response<RpcLogsResponse>,
+}
+
+/// Read the notifications of a capture file, in the order they were received
+///
+/// # Errors
+/// Returns an error if the file cannot be read or a line is not a capture
+pub async fn read(path: &Path) -> Result<Vec<Capture>> {
+    let text = fs::read_to_string(path).await.context(ReadReplayFileSnafu { path })?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).context(DecodeCaptureSnafu { path, line: index + 1 })
+        })
+        .collect()
+}
+
+/// Pace of a capture replay relative to the time it was recorded over
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    /// Keep the recorded gaps between notifications, divided by the factor
+    ///
+    /// Parsed factors are within [`Speed::MIN_FACTOR`] and [`Speed::MAX_FACTOR`].
+    Factor(f64),
+    /// Deliver every notification as soon as the previous one was handled
+    Max,
+}
+
+impl Speed {
+    pub const MIN_FACTOR: f64 = 0.001;
+    pub const MAX_FACTOR: f64 = 1_000_000.0;
+
+    /// Time to wait between notifications received `gap` apart, `None` to not wait at all
+    ///
+    /// Delays too long to be represented, from factors close to 0, saturate to `Duration::MAX`.
+    #[must_use]
+    pub fn delay(self, gap: Duration) -> Option<Duration> {
+        match self {
+            Self::Factor(factor) => Some(
+                Duration::try_from_secs_f64(gap.as_secs_f64() / factor).unwrap_or(Duration::MAX),
+            ),
+            Self::Max => None,
+        }
+    }
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Self::Factor(1.0)
+    }
+}
+
+impl FromStr for Speed {
+    type Err = String;
+
+    /// Parse `max` or a factor such as `10x` or `0.5`, within [`Speed::MIN_FACTOR`] and
+    /// [`Speed::MAX_FACTOR`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("max") {
+            return Ok(Self::Max);
+        }
+        match s.strip_suffix(['x', 'X']).unwrap_or(s).parse::<f64>() {
+            Ok(factor) if (Self::MIN_FACTOR..=Self::MAX_FACTOR).contains(&factor) => {
+                Ok(Self::Factor(factor))
+            }
+            _ => Err(format!(
+                "Invalid speed `{s}`, expected `max` or a factor from {}x to {}x",
+                Self::MIN_FACTOR,
+                Self::MAX_FACTOR
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Factor(factor) => write!(f, "{factor}x"),
+            Self::Max => f.write_str("max"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::rpc_response::RpcResponseContext;
+
+    use super::*;
+
+    fn response(signature: &str) -> Response<RpcLogsResponse> {
+        Response {
+            context: RpcResponseContext { slot: 42, api_version: None },
+            value: RpcLogsResponse {
+                signature: signature.to_string(),
+                err: None,
+                logs: vec!["Program log: Instruction: Buy".to_string()],
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!("max".parse::<Speed>(), Ok(Speed::Max));
+        assert_eq!("10x".parse::<Speed>(), Ok(Speed::Factor(10.0)));
+        assert_eq!("0.5".parse::<Speed>(), Ok(Speed::Factor(0.5)));
+        assert!("0x".parse::<Speed>().is_err());
+        assert!("1e-300x".parse::<Speed>().is_err());
+        assert!("1e300x".parse::<Speed>().is_err());
+        assert!("NaN".parse::<Speed>().is_err());
+        assert!("fast".parse::<Speed>().is_err());
+        assert_eq!(
+            Speed::Factor(10.0).delay(Duration::from_secs(5)),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(Speed::Max.delay(Duration::from_secs(5)), None);
+        assert_eq!(Speed::Factor(1e-300).delay(Duration::from_secs(5)), Some(Duration::MAX));
+        assert_eq!(Speed::Factor(0.0).delay(Duration::from_secs(5)), Some(Duration::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_recorded_responses_read_back_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("captures/capture.ndjson");
+
+        let mut recorder = Recorder::open(&path).await.unwrap();
+        recorder.record(&response("first")).await.unwrap();
+        recorder.record(&response("second")).await.unwrap();
+        drop(recorder);
+        // Appends to the capture of a previous run
+        Recorder::open(&path).await.unwrap().record(&response("third")).await.unwrap();
+
+        let captures = read(&path).await.unwrap();
+        let signatures: Vec<_> =
+            captures.iter().map(|capture| capture.response.value.signature.as_str()).collect();
+        assert_eq!(signatures, ["first", "second", "third"]);
+        assert_eq!(captures[0].response, response("first"));
+        assert!(captures[0].received_at <= captures[2].received_at);
+    }
+
+    #[tokio::test]
+    async fn test_read_reports_malformed_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.ndjson");
+        std::fs::write(&path, "\n{ not json }\n").unwrap();
+
+        let err = read(&path).await.unwrap_err();
+
+        assert!(matches!(err, crate::Error::DecodeCapture { line: 2, .. }), "{err}");
+    }
+}
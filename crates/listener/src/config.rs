@@ -0,0 +1,288 @@
+use std::{fmt, path::PathBuf, str::FromStr, time::Duration};
+
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use async_trait::async_trait;
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_request::RpcRequest,
+};
+use solana_rpc_client::{
+    http_sender::HttpSender,
+    rpc_client::RpcClientConfig,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+
+use crate::{
+    backfill::BackfillConfig,
+    reconnect::ReconnectConfig,
+    sink::{SinkConfig, StdoutSinkConfig},
+    BuildRpcClientSnafu, InvalidHeaderSnafu, Result,
+};
+
+const API_KEY_PARAM: &str = "api-key";
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cluster {
+    #[default]
+    Mainnet,
+    Devnet,
+    Localnet,
+}
+
+impl Cluster {
+    /// Public HTTP JSON-RPC endpoint of the cluster
+    #[must_use]
+    pub const fn url(self) -> &'static str {
+        match self {
+            Self::Mainnet => "https://api.mainnet-beta.solana.com",
+            Self::Devnet => "https://api.devnet.solana.com",
+            Self::Localnet => "http://127.0.0.1:8899",
+        }
+    }
+
+    /// Public websocket endpoint of the cluster
+    #[must_use]
+    pub const fn ws_url(self) -> &'static str {
+        match self {
+            Self::Mainnet => "wss://api.mainnet-beta.solana.com",
+            Self::Devnet => "wss://api.devnet.solana.com",
+            Self::Localnet => "ws://127.0.0.1:8900",
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Self::Mainnet),
+            "devnet" => Ok(Self::Devnet),
+            "localnet" => Ok(Self::Localnet),
+            _ => Err(format!("Unknown cluster `{s}`, expected mainnet, devnet or localnet")),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mainnet => f.write_str("mainnet"),
+            Self::Devnet => f.write_str("devnet"),
+            Self::Localnet => f.write_str("localnet"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Config {
+    #[serde(default = "Config::default_cluster")]
+    pub cluster: Cluster,
+
+    /// Websocket endpoint, defaults to the public endpoint of `cluster`
+    #[serde(default = "Config::default_ws_url")]
+    pub ws_url: Option<String>,
+
+    /// HTTP JSON-RPC endpoint, defaults to the public endpoint of `cluster`
+    #[serde(default = "Config::default_rpc_url")]
+    pub rpc_url: Option<String>,
+
+    /// API key appended to both endpoints as the `api-key` query parameter
+    #[serde(default = "Config::default_api_key")]
+    pub api_key: Option<String>,
+
+    /// Extra headers sent with HTTP JSON-RPC requests, the websocket handshake does not support
+    /// custom headers
+    #[serde(default, with = "http_serde::header_map")]
+    pub headers: HeaderMap,
+
+    #[serde(default)]
+    pub reconnect: ReconnectConfig,
+
+    #[serde(default)]
+    pub backfill: BackfillConfig,
+
+    /// Destinations every decoded event is written to
+    #[serde(default = "Config::default_sinks")]
+    pub sinks: Vec<SinkConfig>,
+
+    /// Capture file every raw notification is appended to, replayable with `heracles replay`
+    #[serde(default = "Config::default_record")]
+    pub record: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cluster: Self::default_cluster(),
+            ws_url: Self::default_ws_url(),
+            rpc_url: Self::default_rpc_url(),
+            api_key: Self::default_api_key(),
+            headers: HeaderMap::new(),
+            reconnect: ReconnectConfig::default(),
+            backfill: BackfillConfig::default(),
+            sinks: Self::default_sinks(),
+            record: Self::default_record(),
+        }
+    }
+}
+
+impl Config {
+    #[inline]
+    #[must_use]
+    pub const fn default_cluster() -> Cluster {
+        Cluster::Mainnet
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_ws_url() -> Option<String> {
+        None
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_rpc_url() -> Option<String> {
+        None
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_api_key() -> Option<String> {
+        None
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn default_sinks() -> Vec<SinkConfig> {
+        vec![SinkConfig::Stdout(StdoutSinkConfig::default())]
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_record() -> Option<PathBuf> {
+        None
+    }
+
+    /// Websocket endpoint without the API key, safe to log
+    #[must_use]
+    pub fn ws_endpoint(&self) -> &str {
+        self.ws_url.as_deref().unwrap_or_else(|| self.cluster.ws_url())
+    }
+
+    /// HTTP JSON-RPC endpoint without the API key, safe to log
+    #[must_use]
+    pub fn rpc_endpoint(&self) -> &str {
+        self.rpc_url.as_deref().unwrap_or_else(|| self.cluster.url())
+    }
+
+    /// Websocket endpoint to connect to, including the API key
+    #[must_use]
+    pub fn ws_url(&self) -> String {
+        self.with_api_key(self.ws_endpoint())
+    }
+
+    /// HTTP JSON-RPC endpoint to connect to, including the API key
+    #[must_use]
+    pub fn rpc_url(&self) -> String {
+        self.with_api_key(self.rpc_endpoint())
+    }
+
+    /// HTTP JSON-RPC client sending the configured `headers`
+    ///
+    /// The URLs of its errors are stripped, so that they can be logged without the API key.
+    ///
+    /// # Errors
+    /// Returns an error if a header is not accepted by the HTTP client, or if the client cannot
+    /// be built
+    pub fn rpc_client(&self) -> Result<RpcClient> {
+        let mut headers = HttpSender::default_headers();
+        for (name, value) in &self.headers {
+            let header = reqwest::header::HeaderName::from_bytes(name.as_ref())
+                .ok()
+                .zip(reqwest::header::HeaderValue::from_bytes(value.as_bytes()).ok())
+                .context(InvalidHeaderSnafu { name: name.as_str() })?;
+            let _unused = headers.append(header.0, header.1);
+        }
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .timeout(RPC_TIMEOUT)
+            .pool_idle_timeout(RPC_TIMEOUT)
+            .build()
+            .context(BuildRpcClientSnafu)?;
+        Ok(RpcClient::new_sender(
+            RedactingSender {
+                sender: HttpSender::new_with_client(self.rpc_url(), client),
+                endpoint: self.rpc_endpoint().to_string(),
+            },
+            RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+        ))
+    }
+
+    fn with_api_key(&self, url: &str) -> String {
+        let Some(api_key) = &self.api_key else {
+            return url.to_string();
+        };
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{url}{separator}{API_KEY_PARAM}={api_key}")
+    }
+}
+
+/// HTTP sender whose errors and URL leave out the API key
+struct RedactingSender {
+    sender: HttpSender,
+    endpoint: String,
+}
+
+#[async_trait]
+impl RpcSender for RedactingSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, ClientError> {
+        self.sender.send(request, params).await.map_err(|err| match err.kind {
+            ClientErrorKind::Reqwest(source) => ClientError {
+                request: err.request,
+                kind: ClientErrorKind::Reqwest(source.without_url()),
+            },
+            kind => ClientError { kind, ..err },
+        })
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.sender.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.endpoint.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rpc_client_errors_leave_out_api_key() {
+        let config = Config {
+            rpc_url: Some("http://127.0.0.1:1".to_string()),
+            api_key: Some("secret".to_string()),
+            ..Config::default()
+        };
+        let client = config.rpc_client().unwrap();
+
+        let err = client.get_slot().await.unwrap_err();
+
+        assert!(matches!(err.kind, ClientErrorKind::Reqwest(_)), "{err}");
+        assert!(!err.to_string().contains("secret"), "{err}");
+        assert!(!format!("{err:?}").contains("secret"), "{err:?}");
+        assert_eq!(client.url(), "http://127.0.0.1:1");
+    }
+}
@@ -1,36 +1,183 @@
-use anchor_client::{solana_sdk::commitment_config::CommitmentConfig, Cluster};
+pub mod backfill;
+pub mod capture;
+mod config;
+mod reconnect;
+pub mod replay;
+pub mod sink;
+mod stream;
+#[cfg(test)]
+pub(crate) mod testing;
+
+use std::{collections::HashSet, path::PathBuf};
+
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, signature::ParseSignatureError,
+};
 use futures_util::StreamExt;
 use snafu::{ResultExt, Snafu};
 use solana_client::{
-    nonblocking::pubsub_client::{PubsubClient, PubsubClientError},
+    client_error::ClientError,
+    nonblocking::{
+        pubsub_client::{PubsubClient, PubsubClientError},
+        rpc_client::RpcClient,
+    },
     rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+    rpc_response::{Response, RpcLogsResponse},
+};
+use tracing::{debug, info, warn};
+
+use self::{
+    backfill::{BackfillConfig, Cursor},
+    capture::Recorder,
+    reconnect::Backoff,
+};
+pub use self::{
+    config::{Cluster, Config},
+    reconnect::ReconnectConfig,
+    sink::EventSink,
+    stream::{subscribe_events, EventStream},
 };
-use tracing::info;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Failed to connect to websocket at {}: {}", url, source))]
-    WebsocketConnection { url: String, source: PubsubClientError },
+    WebsocketConnection {
+        url: String,
+        #[snafu(source(from(PubsubClientError, Box::new)))]
+        source: Box<PubsubClientError>,
+    },
 
     #[snafu(display("Failed to subscribe to logs: {}", source))]
-    LogSubscription { source: PubsubClientError },
+    LogSubscription {
+        #[snafu(source(from(PubsubClientError, Box::new)))]
+        source: Box<PubsubClientError>,
+    },
+
+    #[snafu(display("Failed to build RPC client: {}", source))]
+    BuildRpcClient { source: reqwest::Error },
+
+    #[snafu(display("Invalid RPC header {}", name))]
+    InvalidHeader { name: String },
+
+    #[snafu(display("Failed to fetch the current slot: {}", source))]
+    FetchSlot {
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display("Failed to fetch signatures for address: {}", source))]
+    FetchSignatures {
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display("Failed to fetch transaction {}: {}", signature, source))]
+    FetchTransaction {
+        signature: String,
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display("Invalid transaction signature {}: {}", signature, source))]
+    InvalidSignature { signature: String, source: ParseSignatureError },
+
+    #[snafu(display("Failed to read replay file {}: {}", path.display(), source))]
+    ReadReplayFile { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("Failed to decode replay file {}: {}", path.display(), source))]
+    DecodeReplayFile { path: PathBuf, source: serde_json::Error },
+
+    #[snafu(display("Replayed transaction of slot {} has no signature", slot))]
+    MissingReplaySignature { slot: u64 },
+
+    #[snafu(display("Failed to open capture file {}: {}", path.display(), source))]
+    OpenCapture { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("Failed to write capture file {}: {}", path.display(), source))]
+    WriteCapture { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("Failed to decode line {} of capture file {}: {}", line, path.display(), source))]
+    DecodeCapture { path: PathBuf, line: usize, source: serde_json::Error },
+
+    #[snafu(display("Failed to write events: {}", source))]
+    Sink { source: sink::Error },
+
+    #[snafu(display("Log subscription stream ended"))]
+    StreamEnded,
+
+    #[snafu(display("Giving up after {} failed reconnection attempts: {}", attempts, source))]
+    #[allow(clippy::use_self)]
+    ReconnectLimitReached { attempts: u32, source: Box<Error> },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-/// Subscribe to Solana program logs for the pump_amm program
+/// Subscribe to Solana program logs for the `pump_amm` program and write the decoded events of
+/// every transaction to `sink`
+///
+/// The subscription is supervised: whenever the connection fails or the stream ends, it is
+/// re-established with a jittered exponential backoff as configured by [`Config::reconnect`].
+/// The sink is not flushed on return, which is left to the caller. See [`subscribe_events`] to
+/// consume the events as a stream instead.
+///
+/// Every transaction delivered to `sink` is appended once to [`Config::record`] if set, to be
+/// replayed later with [`replay::Source::Capture`]. Backfilled transactions are recorded as
+/// fetched, and their later notifications are skipped like they are for `sink`.
 ///
 /// # Errors
-/// Returns an error if:
-/// - Cannot connect to the websocket endpoint
-/// - Cannot subscribe to program logs
-/// - Stream ends unexpectedly
-pub async fn subscribe() -> Result<()> {
+/// Returns an error if `sink` fails, if the capture file cannot be written, or once
+/// `reconnect.max_attempts` consecutive attempts have failed to:
+/// - Connect to the websocket endpoint
+/// - Subscribe to program logs
+/// - Keep the stream open
+pub async fn subscribe<S: EventSink>(config: &Config, sink: &mut S) -> Result<()> {
+    let rpc_client = config.rpc_client()?;
+    let mut recorder = match &config.record {
+        Some(path) => Some(Recorder::open(path).await?),
+        None => None,
+    };
+    let mut backoff = Backoff::new(config.reconnect.clone());
+    let mut cursor = None;
+    loop {
+        let err = match subscribe_once(
+            config,
+            &rpc_client,
+            sink,
+            &mut recorder,
+            &mut backoff,
+            &mut cursor,
+        )
+        .await
+        {
+            Ok(()) => Error::StreamEnded,
+            // Reconnecting would not help a failing sink or capture file
+            Err(err @ (Error::Sink { .. } | Error::WriteCapture { .. })) => return Err(err),
+            Err(err) => err,
+        };
+        let Some(delay) = backoff.next_delay() else {
+            return Err(Error::ReconnectLimitReached {
+                attempts: backoff.attempts(),
+                source: Box::new(err),
+            });
+        };
+        warn!("{err}, reconnecting in {delay:?} (attempt {})", backoff.attempts());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn subscribe_once<S: EventSink>(
+    config: &Config,
+    rpc_client: &RpcClient,
+    sink: &mut S,
+    recorder: &mut Option<Recorder>,
+    backoff: &mut Backoff,
+    cursor: &mut Option<Cursor>,
+) -> Result<()> {
     let program_id = pump_amm::ID.to_string();
-    let ws_url = Cluster::Mainnet.ws_url();
 
-    let ws_client =
-        PubsubClient::new(ws_url).await.context(WebsocketConnectionSnafu { url: ws_url })?;
+    let ws_client = PubsubClient::new(&config.ws_url())
+        .await
+        .context(WebsocketConnectionSnafu { url: config.ws_endpoint() })?;
 
     let (mut stream, _) = ws_client
         .logs_subscribe(
@@ -39,13 +186,317 @@ pub async fn subscribe() -> Result<()> {
         )
         .await
         .context(LogSubscriptionSnafu)?;
+    info!("Subscribed to {program_id} logs at {}", config.ws_endpoint());
+
+    // The live stream is buffered while catching up, so anything it delivers that was already
+    // backfilled is skipped
+    let backfilled = match cursor.clone() {
+        Some(last) if config.backfill.enabled => {
+            let recorder = recorder.as_mut();
+            backfill(rpc_client, sink, recorder, &last, &config.backfill, &program_id, cursor)
+                .await?
+        }
+        _ => HashSet::new(),
+    };
 
     while let Some(log) = stream.next().await {
-        let events = pump_amm::parse_logs_response(&log, &program_id);
-        for event in events {
-            info!("Received {event:?}");
+        // Only a subscription that delivers counts as healthy, an endpoint accepting and then
+        // dropping every subscription must still exhaust the attempts
+        backoff.reset();
+        if backfilled.contains(&log.value.signature) {
+            continue;
         }
+        deliver(sink, recorder.as_mut(), &log, &program_id).await?;
+        *cursor = Some(Cursor::new(&log));
     }
 
     Ok(())
 }
+
+async fn backfill<S: EventSink>(
+    rpc_client: &RpcClient,
+    sink: &mut S,
+    mut recorder: Option<&mut Recorder>,
+    last: &Cursor,
+    config: &BackfillConfig,
+    program_id: &str,
+    cursor: &mut Option<Cursor>,
+) -> Result<HashSet<String>> {
+    let missed = backfill::missed_signatures(rpc_client, last, config).await?;
+    info!("Backfilling {} transactions since slot {}", missed.len(), last.slot);
+
+    let mut backfilled = HashSet::with_capacity(missed.len());
+    for status in missed {
+        if let Some(log) = backfill::fetch_logs(rpc_client, &status.signature).await? {
+            deliver(sink, recorder.as_deref_mut(), &log, program_id).await?;
+        }
+        *cursor = Some(Cursor { signature: status.signature.clone(), slot: status.slot });
+        let _unused = backfilled.insert(status.signature);
+    }
+    Ok(backfilled)
+}
+
+async fn deliver<S: EventSink>(
+    sink: &mut S,
+    recorder: Option<&mut Recorder>,
+    log: &Response<RpcLogsResponse>,
+    program_id: &str,
+) -> Result<()> {
+    if let Some(recorder) = recorder {
+        recorder.record(log).await?;
+    }
+    if log.value.err.is_some() {
+        if let Some(reason) = pump_amm::ProgramError::from_logs(&log.value.logs) {
+            info!("Transaction {} rejected by pump_amm: {reason}", log.value.signature);
+        }
+    }
+    let records = pump_amm::parse_logs_response(log, program_id);
+    if records.is_empty() {
+        return Ok(());
+    }
+    debug!("Received {} events of transaction {}", records.len(), log.value.signature);
+    sink.write(&records).await.context(SinkSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pump_amm::{Event, EventRecord};
+    use serde_json::json;
+
+    use pump_amm::testing::MockRpc;
+
+    use super::*;
+    use crate::testing::{
+        buy_logs, notification, transaction_result, MockPubsub, Step, VecSink, BUY_EVENT_DATA,
+    };
+
+    fn config(server: &MockPubsub, max_attempts: u32) -> Config {
+        Config {
+            ws_url: Some(server.url()),
+            rpc_url: Some("http://127.0.0.1:1".to_string()),
+            reconnect: ReconnectConfig {
+                max_attempts: Some(max_attempts),
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+            },
+            backfill: BackfillConfig { enabled: false, ..BackfillConfig::default() },
+            ..Config::default()
+        }
+    }
+
+    fn buy(signature: &str, slot: u64) -> Step {
+        Step::Notify(notification(signature, slot, buy_logs(BUY_EVENT_DATA)))
+    }
+
+    fn signatures(sink: &VecSink) -> Vec<&str> {
+        sink.0.iter().map(|record| record.signature.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_delivers_decoded_events() {
+        let server = MockPubsub::start(vec![vec![
+            Step::Accept,
+            buy("first", 10),
+            Step::Notify(notification("unrelated", 11, vec!["Program log: hello".to_string()])),
+            buy("second", 12),
+            Step::Disconnect,
+        ]])
+        .await;
+        let mut sink = VecSink::default();
+
+        let err = subscribe(&config(&server, 0), &mut sink).await.unwrap_err();
+
+        assert!(
+            matches!(&err, Error::ReconnectLimitReached { source, .. } if matches!(**source, Error::StreamEnded)),
+            "{err}"
+        );
+        assert_eq!(signatures(&sink), ["first", "second"]);
+        assert_eq!(sink.0[1].slot, 12);
+        assert!(matches!(sink.0[0].event, Event::Buy(_)));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["method"], "logsSubscribe");
+        assert_eq!(requests[0]["params"][0]["mentions"][0], pump_amm::ID.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_reconnects_after_disconnect() {
+        let server = MockPubsub::start(vec![
+            vec![Step::Accept, buy("before", 10), Step::Disconnect],
+            vec![Step::Accept, buy("after", 11), Step::Disconnect],
+            vec![Step::Accept, Step::Disconnect],
+            vec![Step::Accept, Step::Disconnect],
+        ])
+        .await;
+        let mut sink = VecSink::default();
+
+        // Each delivered notification resets the attempts, while the third subscription ends
+        // before delivering anything and exhausts them
+        let err = subscribe(&config(&server, 1), &mut sink).await.unwrap_err();
+
+        assert!(matches!(err, Error::ReconnectLimitReached { attempts: 1, .. }), "{err}");
+        assert_eq!(signatures(&sink), ["before", "after"]);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_retries_rejected_subscription() {
+        let server = MockPubsub::start(vec![
+            vec![Step::Reject { code: -32_602, message: "Invalid params".to_string() }],
+            vec![Step::Accept, buy("accepted", 10), Step::Disconnect],
+        ])
+        .await;
+        let mut sink = VecSink::default();
+
+        let err = subscribe(&config(&server, 1), &mut sink).await.unwrap_err();
+
+        // The notification of the accepted subscription reset the attempts, then the stream ended
+        assert!(matches!(err, Error::ReconnectLimitReached { attempts: 1, .. }), "{err}");
+        assert_eq!(signatures(&sink), ["accepted"]);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_gives_up_on_rejected_subscription() {
+        let server = MockPubsub::start(vec![vec![Step::Reject {
+            code: -32_602,
+            message: "Invalid params".to_string(),
+        }]])
+        .await;
+
+        let err = subscribe(&config(&server, 0), &mut VecSink::default()).await.unwrap_err();
+
+        assert!(
+            matches!(&err, Error::ReconnectLimitReached { source, .. } if matches!(**source, Error::LogSubscription { .. })),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_skips_malformed_payloads() {
+        let server = MockPubsub::start(vec![vec![
+            Step::Accept,
+            // Not a logs response, dropped by the client
+            Step::Raw(
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "logsNotification",
+                    "params": { "result": { "unexpected": true }, "subscription": 1 },
+                })
+                .to_string(),
+            ),
+            // Undecodable events, skipped by the parser
+            Step::Notify(notification("garbled", 10, buy_logs("not base64!"))),
+            buy("valid", 11),
+            // Not JSON, the client drops the connection
+            Step::Raw("{ not json".to_string()),
+            buy("never delivered", 12),
+        ]])
+        .await;
+        let mut sink = VecSink::default();
+
+        let err = subscribe(&config(&server, 0), &mut sink).await.unwrap_err();
+
+        assert!(
+            matches!(&err, Error::ReconnectLimitReached { source, .. } if matches!(**source, Error::StreamEnded)),
+            "{err}"
+        );
+        assert_eq!(signatures(&sink), ["valid"]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_stops_on_failing_sink() {
+        struct FailingSink;
+
+        impl EventSink for FailingSink {
+            async fn write(&mut self, _records: &[EventRecord]) -> sink::Result<()> {
+                Err(sink::Error::ChannelClosed)
+            }
+
+            async fn flush(&mut self) -> sink::Result<()> {
+                Ok(())
+            }
+        }
+
+        let server = MockPubsub::start(vec![
+            vec![Step::Accept, buy("first", 10)],
+            vec![Step::Accept, buy("second", 11)],
+        ])
+        .await;
+
+        let err = tokio::time::timeout(
+            Duration::from_secs(10),
+            subscribe(&config(&server, 5), &mut FailingSink),
+        )
+        .await
+        .unwrap()
+        .unwrap_err();
+
+        assert!(matches!(err, Error::Sink { source: sink::Error::ChannelClosed }), "{err}");
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_records_capture() {
+        let server =
+            MockPubsub::start(vec![vec![Step::Accept, buy("recorded", 10), Step::Disconnect]])
+                .await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.ndjson");
+        let config = Config { record: Some(path.clone()), ..config(&server, 0) };
+
+        let _unused = subscribe(&config, &mut VecSink::default()).await.unwrap_err();
+
+        let captures = capture::read(&path).await.unwrap();
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].response, notification("recorded", 10, buy_logs(BUY_EVENT_DATA)));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_records_backfilled_transactions_once() {
+        let [first, missed, live] =
+            [10, 11, 12].map(|_| anchor_client::solana_sdk::signature::Signature::new_unique());
+        let server = MockPubsub::start(vec![
+            vec![Step::Accept, buy(&first.to_string(), 10), Step::Disconnect],
+            // The missed transaction is notified again once subscribed, while it is backfilled
+            vec![
+                Step::Accept,
+                buy(&missed.to_string(), 11),
+                buy(&live.to_string(), 12),
+                Step::Disconnect,
+            ],
+        ])
+        .await;
+        let rpc = MockRpc::start(move |method, params| match method {
+            "getSignaturesForAddress" => Ok(json!([
+                { "signature": missed.to_string(), "slot": 11, "confirmationStatus": "confirmed" },
+                { "signature": first.to_string(), "slot": 10, "confirmationStatus": "confirmed" },
+            ])),
+            "getTransaction" => Ok(transaction_result(params[0].as_str().unwrap(), 11)),
+            method => Err(json!({ "code": -32_601, "message": format!("{method} not found") })),
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.ndjson");
+        let config = Config {
+            rpc_url: Some(rpc.url()),
+            backfill: BackfillConfig::default(),
+            record: Some(path.clone()),
+            ..config(&server, 1)
+        };
+        let mut sink = VecSink::default();
+
+        let _unused = subscribe(&config, &mut sink).await.unwrap_err();
+
+        let expected = [first, missed, live].map(|signature| signature.to_string());
+        assert_eq!(signatures(&sink), expected);
+        let captures = capture::read(&path).await.unwrap();
+        let recorded: Vec<_> =
+            captures.iter().map(|capture| capture.response.value.signature.as_str()).collect();
+        assert_eq!(recorded, expected);
+        assert_eq!(rpc.methods(), ["getSignaturesForAddress", "getTransaction"]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReconnectConfig {
+    /// Consecutive failed connections before giving up, `None` to retry forever
+    ///
+    /// A connection only counts as successful once it delivers a notification.
+    #[serde(default = "ReconnectConfig::default_max_attempts")]
+    pub max_attempts: Option<u32>,
+
+    #[serde(default = "ReconnectConfig::default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+
+    #[serde(default = "ReconnectConfig::default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: Self::default_max_attempts(),
+            initial_backoff_ms: Self::default_initial_backoff_ms(),
+            max_backoff_ms: Self::default_max_backoff_ms(),
+        }
+    }
+}
+
+impl ReconnectConfig {
+    #[inline]
+    #[must_use]
+    pub const fn default_max_attempts() -> Option<u32> {
+        None
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_initial_backoff_ms() -> u64 {
+        500
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_max_backoff_ms() -> u64 {
+        30_000
+    }
+}
+
+/// Jittered exponential backoff between reconnection attempts
+#[derive(Debug)]
+pub struct Backoff {
+    config: ReconnectConfig,
+    attempts: u32,
+}
+
+impl Backoff {
+    pub const fn new(config: ReconnectConfig) -> Self {
+        Self { config, attempts: 0 }
+    }
+
+    /// Number of consecutive failed connections since the last successful one
+    pub const fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Forget previous failures once a subscription has delivered a notification
+    pub const fn reset(&mut self) {
+        self.attempts = 0;
+    }
+
+    /// Delay before the next attempt, `None` once `max_attempts` is exhausted
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.config.max_attempts.is_some_and(|max_attempts| self.attempts >= max_attempts) {
+            return None;
+        }
+        let ceiling = self.ceiling_ms();
+        self.attempts += 1;
+        // Keep at least half of the delay so that clients do not reconnect in lockstep nor
+        // hammer the endpoint
+        Some(Duration::from_millis(rand::thread_rng().gen_range(ceiling / 2..=ceiling)))
+    }
+
+    fn ceiling_ms(&self) -> u64 {
+        let ReconnectConfig { initial_backoff_ms, max_backoff_ms, .. } = self.config;
+        initial_backoff_ms
+            .checked_shl(self.attempts)
+            .filter(|backoff| backoff >> self.attempts == initial_backoff_ms)
+            .map_or(max_backoff_ms, |backoff| backoff.min(max_backoff_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backoff(max_attempts: Option<u32>) -> Backoff {
+        Backoff::new(ReconnectConfig {
+            max_attempts,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+        })
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_up_to_max() {
+        let mut backoff = backoff(None);
+        for ceiling in [100, 200, 400, 800, 1_000, 1_000] {
+            let delay = backoff.next_delay().unwrap();
+            assert!(
+                (Duration::from_millis(ceiling / 2)..=Duration::from_millis(ceiling))
+                    .contains(&delay),
+                "{delay:?} should be within half of {ceiling}ms"
+            );
+        }
+        for _ in 0..100 {
+            assert!(backoff.next_delay().unwrap() <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn test_backoff_gives_up_after_max_attempts() {
+        let mut backoff = backoff(Some(2));
+        assert!(backoff.next_delay().is_some());
+        assert!(backoff.next_delay().is_some());
+        assert!(backoff.next_delay().is_none());
+        assert_eq!(backoff.attempts(), 2);
+
+        backoff.reset();
+        assert!(backoff.next_delay().unwrap() <= Duration::from_millis(100));
+    }
+}
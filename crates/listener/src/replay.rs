@@ -0,0 +1,430 @@
+use std::{fs, ops::RangeInclusive, path::PathBuf};
+
+use anchor_client::solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use serde_json::Value;
+use snafu::{OptionExt, ResultExt};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_transaction_status_client_types::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::{
+    backfill,
+    capture::{self, Speed},
+    deliver, Config, DecodeReplayFileSnafu, EventSink, FetchSignaturesSnafu, FetchSlotSnafu,
+    InvalidSignatureSnafu, MissingReplaySignatureSnafu, ReadReplayFileSnafu, Result,
+};
+
+/// Signatures requested per `getSignaturesForAddress` page when walking a slot range
+const PAGE_SIZE: usize = 1000;
+
+/// Slots behind the tip, about a day, past which walking down to a slot range takes a while
+const FAR_BEHIND_SLOTS: u64 = 216_000;
+
+/// Transactions to run through the parser again
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    /// File with one signature per line, fetched with `getTransaction`
+    Signatures(PathBuf),
+
+    /// Every `pump_amm` transaction that landed within the slots, fetched with
+    /// `getSignaturesForAddress` and `getTransaction`
+    Slots(RangeInclusive<u64>),
+
+    /// File of `getTransaction` results, replayed offline
+    ///
+    /// Either a JSON array or one JSON value per line, each being a result or a whole JSON-RPC
+    /// response.
+    Transactions(PathBuf),
+
+    /// Capture file recorded by the listener, replayed offline at `speed`
+    Capture { path: PathBuf, speed: Speed },
+}
+
+/// Parse the transactions of `source` and write their events to `sink`, in order, returning the
+/// number of transactions replayed
+///
+/// Events are delivered exactly as in live mode, so replaying into an event store rebuilds it.
+/// The sink is not flushed on return, which is left to the caller.
+///
+/// # Errors
+/// Returns an error if `source` cannot be read or fetched, or if `sink` fails
+pub async fn replay<S: EventSink>(config: &Config, source: &Source, sink: &mut S) -> Result<usize> {
+    let program_id = pump_amm::ID.to_string();
+    let mut replayed = 0;
+    match source {
+        Source::Transactions(path) => {
+            let text = fs::read_to_string(path).context(ReadReplayFileSnafu { path })?;
+            for transaction in parse_transactions(&text).context(DecodeReplayFileSnafu { path })? {
+                let signature = signature(&transaction)
+                    .context(MissingReplaySignatureSnafu { slot: transaction.slot })?;
+                if let Some(log) = backfill::logs_response(&signature, &transaction) {
+                    deliver(sink, None, &log, &program_id).await?;
+                    replayed += 1;
+                }
+            }
+        }
+        Source::Capture { path, speed } => {
+            let captures = capture::read(path).await?;
+            let start = Instant::now();
+            let mut first = None;
+            for capture in captures {
+                let first = *first.get_or_insert(capture.received_at);
+                let gap = (capture.received_at - first).to_std().unwrap_or_default();
+                if let Some(delay) = speed.delay(gap) {
+                    tokio::time::sleep_until(start + delay).await;
+                }
+                deliver(sink, None, &capture.response, &program_id).await?;
+                replayed += 1;
+            }
+        }
+        Source::Signatures(path) => {
+            let text = fs::read_to_string(path).context(ReadReplayFileSnafu { path })?;
+            let client = config.rpc_client()?;
+            for signature in parse_signatures(&text) {
+                replayed += replay_signature(&client, signature, sink, &program_id).await?;
+            }
+        }
+        Source::Slots(slots) => {
+            let client = config.rpc_client()?;
+            replayed += replay_slots(&client, slots, sink, &program_id).await?;
+        }
+    }
+    info!("Replayed {replayed} transactions");
+    Ok(replayed)
+}
+
+async fn replay_signature<S: EventSink>(
+    client: &RpcClient,
+    signature: &str,
+    sink: &mut S,
+    program_id: &str,
+) -> Result<usize> {
+    let Some(log) = backfill::fetch_logs(client, signature).await? else {
+        warn!("Transaction {signature} has no log messages, skipping");
+        return Ok(0);
+    };
+    deliver(sink, None, &log, program_id).await?;
+    Ok(1)
+}
+
+/// Replay the `pump_amm` transactions that landed within `slots`, oldest first
+///
+/// Pages run from the most recent transaction backwards, so the walk down to the range start
+/// only keeps the cursor of each page overlapping the range, and those pages are fetched again
+/// from the oldest to replay them in order. Besides a cursor per page, no more than two pages are
+/// held at once whatever the range.
+async fn replay_slots<S: EventSink>(
+    client: &RpcClient,
+    slots: &RangeInclusive<u64>,
+    sink: &mut S,
+    program_id: &str,
+) -> Result<usize> {
+    let tip = client.get_slot().await.context(FetchSlotSnafu)?;
+    let behind = tip.saturating_sub(*slots.start());
+    if behind > FAR_BEHIND_SLOTS {
+        warn!(
+            "Slot {} is {behind} slots behind the tip, every transaction since then is listed \
+             before the replay starts",
+            slots.start()
+        );
+    }
+
+    // The first page is kept rather than fetched again, as new transactions would shift it
+    let first = signatures_page(client, None).await?;
+    let mut cursors = Vec::new();
+    let mut page_len = first.len();
+    let mut oldest = first.last().map(|status| (status.slot, status.signature.clone()));
+    while let Some((slot, signature)) = oldest.take() {
+        if page_len < PAGE_SIZE || slot < *slots.start() {
+            break;
+        }
+        let before = signature.parse::<Signature>().context(InvalidSignatureSnafu { signature })?;
+        let page = signatures_page(client, Some(before)).await?;
+        if page.iter().any(|status| slots.contains(&status.slot)) {
+            cursors.push(before);
+        }
+        page_len = page.len();
+        oldest = page.last().map(|status| (status.slot, status.signature.clone()));
+    }
+
+    let mut replayed = 0;
+    for before in cursors.into_iter().rev() {
+        let page = signatures_page(client, Some(before)).await?;
+        for signature in in_slots(page, slots) {
+            replayed += replay_signature(client, &signature, sink, program_id).await?;
+        }
+    }
+    for signature in in_slots(first, slots) {
+        replayed += replay_signature(client, &signature, sink, program_id).await?;
+    }
+    Ok(replayed)
+}
+
+/// Page of `pump_amm` signatures older than `before`, most recent first
+async fn signatures_page(
+    client: &RpcClient,
+    before: Option<Signature>,
+) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+    client
+        .get_signatures_for_address_with_config(
+            &pump_amm::ID,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(PAGE_SIZE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await
+        .context(FetchSignaturesSnafu)
+}
+
+/// Signatures of the page that landed within `slots`, oldest first
+fn in_slots(
+    page: Vec<RpcConfirmedTransactionStatusWithSignature>,
+    slots: &RangeInclusive<u64>,
+) -> impl Iterator<Item = String> + '_ {
+    page.into_iter()
+        .rev()
+        .filter(|status| slots.contains(&status.slot))
+        .map(|status| status.signature)
+}
+
+/// Signatures listed one per line, skipping blank lines and `#` comments
+fn parse_signatures(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// `getTransaction` results, unwrapped from their JSON-RPC response if needed
+///
+/// Transactions the node did not find, with a `null` result, are skipped.
+fn parse_transactions(
+    text: &str,
+) -> serde_json::Result<Vec<EncodedConfirmedTransactionWithStatusMeta>> {
+    let mut transactions = Vec::new();
+    for value in serde_json::Deserializer::from_str(text).into_iter::<Value>() {
+        let values = match value? {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for mut value in values {
+            if let Some(result) = value.get_mut("result") {
+                value = result.take();
+            }
+            if value.is_null() {
+                continue;
+            }
+            transactions.push(serde_json::from_value(value)?);
+        }
+    }
+    Ok(transactions)
+}
+
+/// First signature of the transaction, which identifies it
+fn signature(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Option<String> {
+    match &transaction.transaction.transaction {
+        EncodedTransaction::Json(transaction) => transaction.signatures.first().cloned(),
+        encoded => encoded
+            .decode()
+            .and_then(|transaction| transaction.signatures.first().map(ToString::to_string)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use async_trait::async_trait;
+    use chrono::DateTime;
+    use pump_amm::Event;
+    use serde_json::json;
+    use solana_client::{client_error::ClientError, rpc_request::RpcRequest};
+    use solana_rpc_client::{
+        rpc_client::RpcClientConfig,
+        rpc_sender::{RpcSender, RpcTransportStats},
+    };
+
+    use super::*;
+    use crate::{
+        capture::Capture,
+        testing::{transaction_result, VecSink},
+    };
+
+    const SIGNATURE: &str =
+        "z91Tb2euJY61DK4x6MLm5bc34LkSse7x2Pb45mRvoAYimDMwMYNATD4Un7Q17iLgfv1z7WBBG6aMSqY2xhgLQvU";
+
+    fn transaction(slot: u64) -> Value {
+        transaction_result(SIGNATURE, slot)
+    }
+
+    #[test]
+    fn test_parse_signatures_skips_blank_lines_and_comments() {
+        let text = format!("# pool creations\n{SIGNATURE}\n\n  {SIGNATURE}  \n");
+
+        assert_eq!(parse_signatures(&text).collect::<Vec<_>>(), [SIGNATURE, SIGNATURE]);
+    }
+
+    #[test]
+    fn test_parse_transactions_accepts_arrays_lines_and_responses() {
+        let array = json!([transaction(1), transaction(2)]).to_string();
+        let lines = format!(
+            "{}\n{}\n{}\n",
+            transaction(1),
+            json!({ "jsonrpc": "2.0", "result": transaction(2), "id": 1 }),
+            json!({ "jsonrpc": "2.0", "result": null, "id": 2 }),
+        );
+
+        for text in [array, lines] {
+            let slots: Vec<_> =
+                parse_transactions(&text).unwrap().iter().map(|tx| tx.slot).collect();
+            assert_eq!(slots, [1, 2]);
+        }
+        assert!(parse_transactions("{ not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_capture_keeps_pace() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.ndjson");
+        let log = backfill::logs_response(
+            SIGNATURE,
+            &serde_json::from_value(transaction(330_012_345)).unwrap(),
+        )
+        .unwrap();
+        let received_at = DateTime::from_timestamp(1_743_407_221, 0).unwrap();
+        let lines: Vec<_> = [received_at, received_at + chrono::Duration::seconds(1)]
+            .into_iter()
+            .map(|received_at| {
+                serde_json::to_string(&Capture { received_at, response: log.clone() }).unwrap()
+            })
+            .collect();
+        fs::write(&path, lines.join("\n")).unwrap();
+        let config = Config::default();
+
+        let mut sink = VecSink::default();
+        let started = std::time::Instant::now();
+        let source = Source::Capture { path: path.clone(), speed: Speed::Factor(100.0) };
+        assert_eq!(replay(&config, &source, &mut sink).await.unwrap(), 2);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(10));
+        assert_eq!(sink.0.len(), 2);
+
+        let mut sink = VecSink::default();
+        let source = Source::Capture { path, speed: Speed::Max };
+        assert_eq!(replay(&config, &source, &mut sink).await.unwrap(), 2);
+        assert_eq!(sink.0.len(), 2);
+    }
+
+    /// Node serving the `pump_amm` history, most recent first, in pages like a real one
+    struct HistorySender {
+        history: Vec<(String, u64)>,
+        signature_requests: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl RpcSender for HistorySender {
+        async fn send(
+            &self,
+            request: RpcRequest,
+            params: Value,
+        ) -> std::result::Result<Value, ClientError> {
+            Ok(match request {
+                RpcRequest::GetSlot => json!(self.history[0].1),
+                RpcRequest::GetSignaturesForAddress => {
+                    let _unused = self.signature_requests.fetch_add(1, Ordering::Relaxed);
+                    let before = params[1]["before"].as_str().map_or(0, |before| {
+                        self.history.iter().position(|(signature, _)| signature == before).unwrap()
+                            + 1
+                    });
+                    let limit = usize::try_from(params[1]["limit"].as_u64().unwrap()).unwrap();
+                    let page: Vec<_> = self.history[before..]
+                        .iter()
+                        .take(limit)
+                        .map(|(signature, slot)| {
+                            json!({
+                                "signature": signature,
+                                "slot": slot,
+                                "err": null,
+                                "memo": null,
+                                "blockTime": null,
+                                "confirmationStatus": "confirmed",
+                            })
+                        })
+                        .collect();
+                    json!(page)
+                }
+                RpcRequest::GetTransaction => {
+                    let signature = params[0].as_str().unwrap();
+                    let (_, slot) =
+                        self.history.iter().find(|(other, _)| other == signature).unwrap();
+                    transaction(*slot)
+                }
+                request => panic!("Unexpected request {request}"),
+            })
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "history".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_slots_streams_pages_oldest_first() {
+        // Four transactions per slot, so the range spans the second and third pages only
+        let history: Vec<_> = (0..3500_u64)
+            .map(|index| (Signature::new_unique().to_string(), 5000 - index / 4))
+            .collect();
+        let expected: Vec<_> = history
+            .iter()
+            .rev()
+            .filter(|(_, slot)| (4500..=4700).contains(slot))
+            .map(|(signature, _)| signature.clone())
+            .collect();
+        let signature_requests = Arc::new(AtomicUsize::new(0));
+        let sender = HistorySender { history, signature_requests: Arc::clone(&signature_requests) };
+        let client = RpcClient::new_sender(sender, RpcClientConfig::default());
+        let mut sink = VecSink::default();
+
+        let replayed = replay_slots(&client, &(4500..=4700), &mut sink, &pump_amm::ID.to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(replayed, expected.len());
+        let signatures: Vec<_> = sink.0.iter().map(|record| record.signature.clone()).collect();
+        assert_eq!(signatures, expected);
+        // The first page, a walk down the two pages within the range, then fetching them again,
+        // without ever reaching the fourth page
+        assert_eq!(signature_requests.load(Ordering::Relaxed), 5);
+    }
+
+    #[tokio::test]
+    async fn test_replay_transactions_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.json");
+        fs::write(&path, json!([transaction(330_012_345)]).to_string()).unwrap();
+        // No endpoint is reachable, offline replay must not need one
+        let config =
+            Config { rpc_url: Some("http://127.0.0.1:1".to_string()), ..Config::default() };
+        let mut sink = VecSink::default();
+
+        let replayed = replay(&config, &Source::Transactions(path), &mut sink).await.unwrap();
+
+        assert_eq!(replayed, 1);
+        assert_eq!(sink.0.len(), 1);
+        assert_eq!(sink.0[0].signature, SIGNATURE);
+        assert_eq!(sink.0[0].slot, 330_012_345);
+        assert!(matches!(sink.0[0].event, Event::Buy(_)));
+    }
+}
@@ -0,0 +1,90 @@
+use pump_amm::EventRecord;
+use serde::{Deserialize, Serialize};
+use snafu::OptionExt;
+use tokio::sync::mpsc;
+
+use super::{ChannelClosedSnafu, EventSink, Result};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChannelSinkConfig {
+    /// Records buffered before the listener waits for the consumer, at least 1
+    #[serde(default = "ChannelSinkConfig::default_capacity")]
+    pub capacity: usize,
+}
+
+impl Default for ChannelSinkConfig {
+    fn default() -> Self {
+        Self { capacity: Self::default_capacity() }
+    }
+}
+
+impl ChannelSinkConfig {
+    #[inline]
+    #[must_use]
+    pub const fn default_capacity() -> usize {
+        1024
+    }
+}
+
+/// Sends every record to an in-process consumer
+#[derive(Debug)]
+pub struct ChannelSink {
+    sender: mpsc::Sender<EventRecord>,
+}
+
+impl ChannelSink {
+    /// Create a sink together with the receiver its records are delivered to
+    ///
+    /// A `capacity` of 0 is raised to 1, as the channel needs room for at least one record.
+    #[must_use]
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<EventRecord>) {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        (Self { sender }, receiver)
+    }
+}
+
+impl EventSink for ChannelSink {
+    async fn write(&mut self, records: &[EventRecord]) -> Result<()> {
+        for record in records {
+            self.sender.send(record.clone()).await.ok().context(ChannelClosedSnafu)?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::{tests::record, Error};
+
+    #[tokio::test]
+    async fn test_channel_sink_delivers_records_in_order() {
+        let (mut sink, mut receiver) = ChannelSink::new(2);
+
+        sink.write(&[record("first"), record("second")]).await.unwrap();
+
+        assert_eq!(receiver.recv().await.unwrap().signature, "first");
+        assert_eq!(receiver.recv().await.unwrap().signature, "second");
+    }
+
+    #[tokio::test]
+    async fn test_channel_sink_with_zero_capacity_buffers_one_record() {
+        let (mut sink, mut receiver) = ChannelSink::new(0);
+
+        sink.write(&[record("first")]).await.unwrap();
+
+        assert_eq!(receiver.recv().await.unwrap().signature, "first");
+    }
+
+    #[tokio::test]
+    async fn test_channel_sink_fails_once_receiver_is_dropped() {
+        let (mut sink, receiver) = ChannelSink::new(1);
+        drop(receiver);
+
+        assert!(matches!(sink.write(&[record("first")]).await, Err(Error::ChannelClosed)));
+    }
+}
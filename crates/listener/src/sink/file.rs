@@ -0,0 +1,198 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use pump_amm::{AmountEncoding, EventRecord};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncWriteExt, BufWriter},
+};
+
+use super::{
+    EventSink, OpenFileSnafu, Result, RotateFileSnafu, SerializeRecordSnafu, WriteFileSnafu,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileSinkConfig {
+    #[serde(default = "FileSinkConfig::default_path")]
+    pub path: PathBuf,
+
+    /// Size after which the file is rotated to `<path>.1`
+    #[serde(default = "FileSinkConfig::default_max_bytes")]
+    pub max_bytes: u64,
+
+    /// Rotated files kept next to the current one, the oldest being removed
+    #[serde(default = "FileSinkConfig::default_max_files")]
+    pub max_files: usize,
+
+    #[serde(default)]
+    pub amounts: AmountEncoding,
+}
+
+impl Default for FileSinkConfig {
+    fn default() -> Self {
+        Self {
+            path: Self::default_path(),
+            max_bytes: Self::default_max_bytes(),
+            max_files: Self::default_max_files(),
+            amounts: AmountEncoding::default(),
+        }
+    }
+}
+
+impl FileSinkConfig {
+    #[inline]
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        PathBuf::from("events.jsonl")
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_max_bytes() -> u64 {
+        100 * 1024 * 1024
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_max_files() -> usize {
+        5
+    }
+}
+
+/// Appends every record as a line of JSON to a file rotated by size
+#[derive(Debug)]
+pub struct FileSink {
+    config: FileSinkConfig,
+    file: Option<BufWriter<File>>,
+    len: u64,
+    line: Vec<u8>,
+}
+
+impl FileSink {
+    #[must_use]
+    pub const fn new(config: FileSinkConfig) -> Self {
+        Self { config, file: None, len: 0, line: Vec::new() }
+    }
+
+    async fn open(&mut self) -> Result<&mut BufWriter<File>> {
+        let path = &self.config.path;
+        let file = if let Some(file) = self.file.take() {
+            file
+        } else {
+            if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                fs::create_dir_all(parent).await.context(OpenFileSnafu { path })?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await
+                .context(OpenFileSnafu { path })?;
+            self.len = file.metadata().await.context(OpenFileSnafu { path })?.len();
+            BufWriter::new(file)
+        };
+        Ok(self.file.insert(file))
+    }
+
+    /// Shift `<path>.N` to `<path>.N+1`, dropping the oldest, and move the current file to
+    /// `<path>.1`
+    async fn rotate(&mut self) -> Result<()> {
+        let path = &self.config.path;
+        if let Some(mut file) = self.file.take() {
+            file.flush().await.context(WriteFileSnafu { path })?;
+        }
+        for index in (1..self.config.max_files).rev() {
+            rename_if_exists(&rotated(path, index), &rotated(path, index + 1)).await?;
+        }
+        if self.config.max_files == 0 {
+            fs::remove_file(path).await.context(RotateFileSnafu { path })?;
+        } else {
+            rename_if_exists(path, &rotated(path, 1)).await?;
+        }
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl EventSink for FileSink {
+    async fn write(&mut self, records: &[EventRecord]) -> Result<()> {
+        for record in records {
+            self.line.clear();
+            serde_json::to_writer(&mut self.line, &record.serialize_with(self.config.amounts))
+                .context(SerializeRecordSnafu)?;
+            self.line.push(b'\n');
+
+            // Opening picks up the size of a file left by a previous run
+            let _unused = self.open().await?;
+            let line_len = self.line.len() as u64;
+            if self.len > 0 && self.len + line_len > self.config.max_bytes {
+                self.rotate().await?;
+            }
+            let line = std::mem::take(&mut self.line);
+            let written = self.open().await?.write_all(&line).await;
+            self.line = line;
+            written.context(WriteFileSnafu { path: &self.config.path })?;
+            self.len += line_len;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush().await.context(WriteFileSnafu { path: &self.config.path }),
+            None => Ok(()),
+        }
+    }
+}
+
+fn rotated(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{index}"));
+    PathBuf::from(rotated)
+}
+
+async fn rename_if_exists(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to).await {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            Err(err).context(RotateFileSnafu { path: from })
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::record;
+
+    #[tokio::test]
+    async fn test_file_sink_rotates_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let mut sink = FileSink::new(FileSinkConfig {
+            path: path.clone(),
+            max_bytes: 1,
+            max_files: 2,
+            amounts: AmountEncoding::default(),
+        });
+
+        for signature in ["first", "second", "third", "fourth"] {
+            sink.write(&[record(signature)]).await.unwrap();
+        }
+        sink.flush().await.unwrap();
+
+        let signature = |path: &Path| -> String {
+            let line = std::fs::read_to_string(path).unwrap();
+            let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+            value["signature"].as_str().unwrap().to_string()
+        };
+        assert_eq!(signature(&path), "fourth");
+        assert_eq!(signature(&rotated(&path, 1)), "third");
+        assert_eq!(signature(&rotated(&path, 2)), "second");
+        assert!(!rotated(&path, 3).exists());
+    }
+}
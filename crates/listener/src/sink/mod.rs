@@ -0,0 +1,149 @@
+mod channel;
+mod file;
+mod stdout;
+
+use std::{io, path::PathBuf};
+
+use pump_amm::EventRecord;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use tokio::sync::mpsc;
+
+pub use self::{
+    channel::{ChannelSink, ChannelSinkConfig},
+    file::{FileSink, FileSinkConfig},
+    stdout::{StdoutSink, StdoutSinkConfig},
+};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Failed to serialize event record: {}", source))]
+    SerializeRecord { source: serde_json::Error },
+
+    #[snafu(display("Failed to write events to stdout: {}", source))]
+    WriteStdout { source: io::Error },
+
+    #[snafu(display("Failed to open event file {}: {}", path.display(), source))]
+    OpenFile { path: PathBuf, source: io::Error },
+
+    #[snafu(display("Failed to write event file {}: {}", path.display(), source))]
+    WriteFile { path: PathBuf, source: io::Error },
+
+    #[snafu(display("Failed to rotate event file {}: {}", path.display(), source))]
+    RotateFile { path: PathBuf, source: io::Error },
+
+    #[snafu(display("Event channel closed by its consumer"))]
+    ChannelClosed,
+
+    /// Failure of a sink implemented outside of this crate
+    #[snafu(display("{}", source))]
+    Custom { source: Box<dyn std::error::Error + Send + Sync> },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Destination of the events decoded by the listener
+///
+/// Records are written in batches, one per transaction, in the order the transactions were
+/// received. A failing sink stops the listener.
+pub trait EventSink {
+    /// Write the records of one transaction
+    ///
+    /// # Errors
+    /// Returns an error if the records cannot be delivered
+    async fn write(&mut self, records: &[EventRecord]) -> Result<()>;
+
+    /// Deliver anything still buffered, called before the listener exits
+    ///
+    /// # Errors
+    /// Returns an error if the buffered records cannot be delivered
+    async fn flush(&mut self) -> Result<()>;
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// One JSON object per line on stdout
+    Stdout(StdoutSinkConfig),
+    /// One JSON object per line in a size-rotated file
+    File(FileSinkConfig),
+    /// Bounded channel read by an in-process consumer
+    Channel(ChannelSinkConfig),
+}
+
+#[derive(Debug)]
+enum Sink {
+    Stdout(StdoutSink),
+    File(FileSink),
+    Channel(ChannelSink),
+}
+
+/// Fans records out to every configured sink
+#[derive(Debug, Default)]
+pub struct Sinks {
+    sinks: Vec<Sink>,
+}
+
+impl Sinks {
+    /// Build the configured sinks, returning the receiving end of every channel sink
+    #[must_use]
+    pub fn new(configs: &[SinkConfig]) -> (Self, Vec<mpsc::Receiver<EventRecord>>) {
+        let mut receivers = Vec::new();
+        let sinks = configs
+            .iter()
+            .map(|config| match config {
+                SinkConfig::Stdout(config) => Sink::Stdout(StdoutSink::new(config)),
+                SinkConfig::File(config) => Sink::File(FileSink::new(config.clone())),
+                SinkConfig::Channel(config) => {
+                    let (sink, receiver) = ChannelSink::new(config.capacity);
+                    receivers.push(receiver);
+                    Sink::Channel(sink)
+                }
+            })
+            .collect();
+        (Self { sinks }, receivers)
+    }
+}
+
+impl EventSink for Sinks {
+    async fn write(&mut self, records: &[EventRecord]) -> Result<()> {
+        for sink in &mut self.sinks {
+            match sink {
+                Sink::Stdout(sink) => sink.write(records).await?,
+                Sink::File(sink) => sink.write(records).await?,
+                Sink::Channel(sink) => sink.write(records).await?,
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        for sink in &mut self.sinks {
+            match sink {
+                Sink::Stdout(sink) => sink.flush().await?,
+                Sink::File(sink) => sink.flush().await?,
+                Sink::Channel(sink) => sink.flush().await?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use pump_amm::{pump_amm::events::DisableEvent, Event, EventRecord};
+
+    pub fn record(signature: &str) -> EventRecord {
+        EventRecord {
+            signature: signature.to_string(),
+            slot: 1,
+            event_index: 0,
+            invoker: None,
+            cpi_depth: 1,
+            instruction_index: 0,
+            tx_failed: false,
+            event: Event::Disable(DisableEvent::default()),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use pump_amm::{AmountEncoding, EventRecord};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use tokio::io::{AsyncWriteExt, Stdout};
+
+use super::{EventSink, Result, SerializeRecordSnafu, WriteStdoutSnafu};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StdoutSinkConfig {
+    #[serde(default)]
+    pub amounts: AmountEncoding,
+}
+
+/// Writes every record as a line of JSON on stdout
+#[derive(Debug)]
+pub struct StdoutSink {
+    stdout: Stdout,
+    amounts: AmountEncoding,
+    buffer: Vec<u8>,
+}
+
+impl StdoutSink {
+    #[must_use]
+    pub fn new(config: &StdoutSinkConfig) -> Self {
+        Self { stdout: tokio::io::stdout(), amounts: config.amounts, buffer: Vec::new() }
+    }
+}
+
+impl EventSink for StdoutSink {
+    async fn write(&mut self, records: &[EventRecord]) -> Result<()> {
+        self.buffer.clear();
+        for record in records {
+            serde_json::to_writer(&mut self.buffer, &record.serialize_with(self.amounts))
+                .context(SerializeRecordSnafu)?;
+            self.buffer.push(b'\n');
+        }
+        self.stdout.write_all(&self.buffer).await.context(WriteStdoutSnafu)?;
+        // Consumers piping stdout expect every transaction as soon as it is received
+        self.stdout.flush().await.context(WriteStdoutSnafu)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.stdout.flush().await.context(WriteStdoutSnafu)
+    }
+}
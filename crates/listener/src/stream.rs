@@ -0,0 +1,101 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::Stream;
+use pump_amm::EventRecord;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::{
+    sink::{self, ChannelSinkConfig, EventSink},
+    subscribe, Config, Result,
+};
+
+/// Decoded events of the `pump_amm` program, as returned by [`subscribe_events`]
+///
+/// The stream ends after yielding the error the listener gave up with. Dropping it stops the
+/// listener.
+#[derive(Debug)]
+pub struct EventStream {
+    receiver: mpsc::Receiver<Result<EventRecord>>,
+    listener: JoinHandle<()>,
+}
+
+impl Stream for EventStream {
+    type Item = Result<EventRecord>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+/// Subscribe to the `pump_amm` program logs as a stream of decoded events
+///
+/// The listener runs as a background task, reconnecting and backfilling as configured, while
+/// [`Config::sinks`] are ignored. Events are buffered up to the default channel sink capacity
+/// before the listener waits for the stream to be polled.
+///
+/// # Panics
+/// Panics if called outside of a tokio runtime
+#[must_use]
+pub fn subscribe_events(config: Config) -> EventStream {
+    let (sender, receiver) = mpsc::channel(ChannelSinkConfig::default_capacity());
+    let listener = tokio::spawn(async move {
+        let mut sink = StreamSink { sender };
+        if let Err(err) = subscribe(&config, &mut sink).await {
+            let _unused = sink.sender.send(Err(err)).await;
+        }
+    });
+    EventStream { receiver, listener }
+}
+
+struct StreamSink {
+    sender: mpsc::Sender<Result<EventRecord>>,
+}
+
+impl EventSink for StreamSink {
+    async fn write(&mut self, records: &[EventRecord]) -> sink::Result<()> {
+        for record in records {
+            if self.sender.send(Ok(record.clone())).await.is_err() {
+                return Err(sink::Error::ChannelClosed);
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> sink::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{Error, ReconnectConfig};
+
+    #[tokio::test]
+    async fn test_event_stream_ends_with_listener_error() {
+        let config = Config {
+            ws_url: Some("ws://127.0.0.1:1".to_string()),
+            reconnect: ReconnectConfig { max_attempts: Some(0), ..ReconnectConfig::default() },
+            ..Config::default()
+        };
+
+        let mut events = subscribe_events(config);
+
+        assert!(matches!(
+            events.next().await,
+            Some(Err(Error::ReconnectLimitReached { attempts: 0, .. }))
+        ));
+        assert!(events.next().await.is_none());
+    }
+}
@@ -0,0 +1,216 @@
+//! Test support: a local websocket server speaking the `logsSubscribe` protocol, and helpers to
+//! build the notifications it serves
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use pump_amm::EventRecord;
+use serde_json::{json, Value};
+use solana_client::rpc_response::{Response, RpcLogsResponse, RpcResponseContext};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tokio_tungstenite::{
+    tungstenite::{protocol::CloseFrame, Message},
+    WebSocketStream,
+};
+
+use crate::{sink, EventSink};
+
+/// Base64 payload of a `BuyEvent` emitted on mainnet
+pub const BUY_EVENT_DATA: &str = "Z/RSHyz1d3d1SOpnAAAAAGb4hZQHAAAAia4KGQAAAAAAAAAAAAAAAITRnPLlBQAAfIUSmXIuAACsgqb2mAAAAOyx+hgAAAAAFAAAAAAAAAAWygwAAAAAAAUAAAAAAAAAhjIDAAAAAAACfAcZAAAAAIiuChkAAAAAs2KoY0K+8wW11Mzg7EPJF5YwXehmJXMsVZt7MEYcSPIU6zbh3Tj7uDLsYRbIHIH8Nxc2EsCB0aH03gYW/tnNAIr01Uaez9OsUAzDGS6qy0F7QYyFfp5dj72V3hxIbzYm0bqMzqITUWXmdMfp+z3UPNRElWs2FFc6IsqBgy9R1gFjg3MADqIssmTTSv9koEte+r+7dN3NBImXsZgVR9fREAe0ZyjFA6fIFZjsUWe5tjKg2nvc6Y8HxZZ7EO1veKHO";
+
+/// Logs of a top level `pump_amm` buy carrying `data` as its event
+pub fn buy_logs(data: &str) -> Vec<String> {
+    vec![
+        format!("Program {} invoke [1]", pump_amm::ID),
+        "Program log: Instruction: Buy".to_string(),
+        format!("Program data: {data}"),
+        format!("Program {} success", pump_amm::ID),
+    ]
+}
+
+/// `logsSubscribe` notification of a transaction
+pub fn notification(signature: &str, slot: u64, logs: Vec<String>) -> Response<RpcLogsResponse> {
+    Response {
+        context: RpcResponseContext { slot, api_version: None },
+        value: RpcLogsResponse { signature: signature.to_string(), err: None, logs },
+    }
+}
+
+/// `getTransaction` result of a buy landed at `slot`
+pub fn transaction_result(signature: &str, slot: u64) -> Value {
+    json!({
+        "slot": slot,
+        "blockTime": 1_743_407_221,
+        "transaction": {
+            "signatures": [signature],
+            "message": {
+                "header": {
+                    "numRequiredSignatures": 1,
+                    "numReadonlySignedAccounts": 0,
+                    "numReadonlyUnsignedAccounts": 0,
+                },
+                "accountKeys": [],
+                "recentBlockhash": "11111111111111111111111111111111",
+                "instructions": [],
+            },
+        },
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "logMessages": buy_logs(BUY_EVENT_DATA),
+        },
+    })
+}
+
+/// Sink keeping every record it is given
+#[derive(Debug, Default)]
+pub struct VecSink(pub Vec<EventRecord>);
+
+impl EventSink for VecSink {
+    async fn write(&mut self, records: &[EventRecord]) -> sink::Result<()> {
+        self.0.extend_from_slice(records);
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> sink::Result<()> {
+        Ok(())
+    }
+}
+
+/// What the server does next on a connection
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Answer the next subscription request with a subscription id
+    Accept,
+    /// Answer the next subscription request with a JSON-RPC error
+    Reject { code: i64, message: String },
+    /// Send a `logsNotification` for the accepted subscription
+    Notify(Response<RpcLogsResponse>),
+    /// Send a text frame as is, to serve malformed payloads
+    Raw(String),
+    /// Close the connection
+    Disconnect,
+}
+
+/// Websocket server serving one script per connection, in the order the connections arrive
+///
+/// A connection whose script ran out stays open until the client leaves. Connections beyond the
+/// last script are dropped before the websocket handshake.
+#[derive(Debug)]
+pub struct MockPubsub {
+    url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+    server: JoinHandle<()>,
+}
+
+impl MockPubsub {
+    /// # Panics
+    /// Panics if no local port can be bound
+    pub async fn start(scripts: Vec<Vec<Step>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock pubsub");
+        let url = format!("ws://{}", listener.local_addr().expect("Failed to get local address"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let mut scripts = VecDeque::from(scripts);
+        let server = tokio::spawn({
+            let requests = Arc::clone(&requests);
+            async move {
+                let mut connection = 0;
+                while let Ok((stream, _)) = listener.accept().await {
+                    let Some(script) = scripts.pop_front() else {
+                        drop(stream);
+                        continue;
+                    };
+                    connection += 1;
+                    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+                        continue;
+                    };
+                    let _unused =
+                        tokio::spawn(serve(ws, script, connection, Arc::clone(&requests)));
+                }
+            }
+        });
+        Self { url, requests, server }
+    }
+
+    #[must_use]
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Subscription requests received so far, across connections
+    #[must_use]
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+impl Drop for MockPubsub {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn serve<S>(
+    mut ws: WebSocketStream<S>,
+    script: Vec<Step>,
+    subscription: u64,
+    requests: Arc<Mutex<Vec<Value>>>,
+) where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    for step in script {
+        let message = match step {
+            Step::Accept | Step::Reject { .. } => {
+                let Some(request) = next_request(&mut ws).await else {
+                    return;
+                };
+                let id = request["id"].clone();
+                requests.lock().unwrap_or_else(PoisonError::into_inner).push(request);
+                match step {
+                    Step::Reject { code, message } => json!({
+                        "jsonrpc": "2.0",
+                        "error": { "code": code, "message": message },
+                        "id": id,
+                    }),
+                    _ => json!({ "jsonrpc": "2.0", "result": subscription, "id": id }),
+                }
+                .to_string()
+            }
+            Step::Notify(response) => json!({
+                "jsonrpc": "2.0",
+                "method": "logsNotification",
+                "params": { "result": response, "subscription": subscription },
+            })
+            .to_string(),
+            Step::Raw(text) => text,
+            Step::Disconnect => {
+                let frame = CloseFrame { code: 1000.into(), reason: "".into() };
+                let _unused = ws.close(Some(frame)).await;
+                return;
+            }
+        };
+        if ws.send(Message::Text(message)).await.is_err() {
+            return;
+        }
+    }
+    // Hold the connection until the client leaves
+    while let Some(Ok(_)) = ws.next().await {}
+}
+
+async fn next_request<S>(ws: &mut WebSocketStream<S>) -> Option<Value>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    while let Some(Ok(message)) = ws.next().await {
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).ok();
+        }
+    }
+    None
+}
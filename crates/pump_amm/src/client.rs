@@ -0,0 +1,469 @@
+use anchor_client::{
+    anchor_lang::{prelude::Pubkey, AccountDeserialize, Discriminator},
+    solana_account_decoder::UiAccountEncoding,
+    solana_sdk::commitment_config::CommitmentConfig,
+};
+use snafu::{ensure, OptionExt, ResultExt};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+
+use crate::{
+    instruction::{PoolAccounts, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+    pda,
+    pump_amm::accounts::{GlobalConfig, Pool},
+    quote::Reserves,
+    AccountNotFoundSnafu, DeserializeAccountSnafu, FetchAccountSnafu, FetchProgramAccountsSnafu,
+    InvalidTokenAccountSnafu, Result, UnexpectedMintOwnerSnafu, UnexpectedOwnerSnafu, ID,
+};
+
+/// Offset of `Pool::creator`, after the discriminator, `pool_bump` and `index`
+const POOL_CREATOR_OFFSET: usize = 8 + 1 + 2;
+const POOL_BASE_MINT_OFFSET: usize = POOL_CREATOR_OFFSET + 32;
+const POOL_QUOTE_MINT_OFFSET: usize = POOL_BASE_MINT_OFFSET + 32;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 32 + 32;
+
+/// Pools listed by [`Client::pools`], every field left to `None` matching any pool
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolFilter {
+    pub base_mint: Option<Pubkey>,
+    pub quote_mint: Option<Pubkey>,
+    pub creator: Option<Pubkey>,
+}
+
+impl PoolFilter {
+    fn rpc_filters(&self) -> Vec<RpcFilterType> {
+        let mut filters =
+            vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, Pool::DISCRIMINATOR.to_vec()))];
+        for (offset, key) in [
+            (POOL_CREATOR_OFFSET, self.creator),
+            (POOL_BASE_MINT_OFFSET, self.base_mint),
+            (POOL_QUOTE_MINT_OFFSET, self.quote_mint),
+        ] {
+            if let Some(key) = key {
+                filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    offset,
+                    key.to_bytes().to_vec(),
+                )));
+            }
+        }
+        filters
+    }
+}
+
+/// Reads `pump_amm` accounts over HTTP JSON-RPC
+pub struct Client {
+    rpc: RpcClient,
+}
+
+impl Client {
+    #[must_use]
+    pub const fn new(rpc: RpcClient) -> Self {
+        Self { rpc }
+    }
+
+    #[must_use]
+    pub const fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    /// Fetch the pool at `address`
+    ///
+    /// # Errors
+    /// Returns an error if the account cannot be fetched, does not exist, is not owned by
+    /// `pump_amm` or is not a pool
+    pub async fn pool(&self, address: &Pubkey) -> Result<Pool> {
+        self.account(address).await
+    }
+
+    /// Fetch the pool at `address` together with the token programs owning its mints
+    ///
+    /// # Errors
+    /// Returns an error if the pool cannot be fetched, or if a mint does not exist or is not
+    /// owned by the SPL Token or Token-2022 program
+    pub async fn pool_accounts(&self, address: &Pubkey) -> Result<PoolAccounts> {
+        let pool = self.pool(address).await?;
+        let mints = [pool.base_mint, pool.quote_mint];
+        let accounts = self
+            .rpc
+            .get_multiple_accounts(&mints)
+            .await
+            .context(FetchAccountSnafu { address: pool.base_mint })?;
+        let mut programs = mints.iter().zip(accounts).map(|(address, account)| {
+            let account = account.context(AccountNotFoundSnafu { address: *address })?;
+            ensure!(
+                [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID].contains(&account.owner),
+                UnexpectedMintOwnerSnafu { address: *address, owner: account.owner }
+            );
+            Ok(account.owner)
+        });
+        match (programs.next(), programs.next()) {
+            (Some(base), Some(quote)) => {
+                Ok(PoolAccounts::new(*address, pool).with_token_programs(base?, quote?))
+            }
+            _ => AccountNotFoundSnafu { address: pool.quote_mint }.fail(),
+        }
+    }
+
+    /// List the pools matching `filter` with their addresses
+    ///
+    /// # Errors
+    /// Returns an error if `getProgramAccounts` fails or returns an account that is not a pool
+    pub async fn pools(&self, filter: &PoolFilter) -> Result<Vec<(Pubkey, Pool)>> {
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(
+                &ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(filter.rpc_filters()),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        ..RpcAccountInfoConfig::default()
+                    },
+                    ..RpcProgramAccountsConfig::default()
+                },
+            )
+            .await
+            .context(FetchProgramAccountsSnafu)?;
+        accounts
+            .into_iter()
+            .map(|(address, account)| Ok((address, deserialize(&address, &account.data)?)))
+            .collect()
+    }
+
+    /// Fetch the global config
+    ///
+    /// # Errors
+    /// Returns an error if the account cannot be fetched, does not exist, is not owned by
+    /// `pump_amm` or is not a global config
+    pub async fn global_config(&self) -> Result<GlobalConfig> {
+        self.account(&pda::global_config_address()).await
+    }
+
+    /// Current reserves of `pool`, read from its token accounts
+    ///
+    /// # Errors
+    /// Returns an error if the token accounts cannot be fetched or are not token accounts of the
+    /// SPL Token or Token-2022 program
+    pub async fn reserves(&self, pool: &Pool) -> Result<Reserves> {
+        let addresses = [pool.pool_base_token_account, pool.pool_quote_token_account];
+        let accounts = self
+            .rpc
+            .get_multiple_accounts(&addresses)
+            .await
+            .context(FetchAccountSnafu { address: pool.pool_base_token_account })?;
+        let mut amounts = addresses.iter().zip(accounts).map(|(address, account)| {
+            let account = account.context(AccountNotFoundSnafu { address: *address })?;
+            [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID]
+                .contains(&account.owner)
+                .then(|| token_amount(&account.data))
+                .flatten()
+                .context(InvalidTokenAccountSnafu { address: *address })
+        });
+        match (amounts.next(), amounts.next()) {
+            (Some(base), Some(quote)) => Ok(Reserves { base: base?, quote: quote? }),
+            _ => AccountNotFoundSnafu { address: pool.pool_quote_token_account }.fail(),
+        }
+    }
+
+    async fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(address, CommitmentConfig::confirmed())
+            .await
+            .context(FetchAccountSnafu { address: *address })?
+            .value
+            .context(AccountNotFoundSnafu { address: *address })?;
+        // Anyone can create an account holding the same bytes, only the program's own are trusted
+        ensure!(
+            account.owner == ID,
+            UnexpectedOwnerSnafu { address: *address, owner: account.owner }
+        );
+        deserialize(address, &account.data)
+    }
+}
+
+/// Amount held by an SPL Token or Token-2022 account, stored after its mint and owner
+fn token_amount(data: &[u8]) -> Option<u64> {
+    let amount = data.get(TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8)?;
+    Some(u64::from_le_bytes(amount.try_into().ok()?))
+}
+
+fn deserialize<T: AccountDeserialize>(address: &Pubkey, mut data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut data).context(DeserializeAccountSnafu { address: *address })
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_client::anchor_lang::AccountSerialize;
+    use serde_json::{json, Value};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_rpc_client::mock_sender::Mocks;
+
+    use super::*;
+    use crate::{
+        testing::{program_account, token_account, ui_account, with_context, MockRpc},
+        Error,
+    };
+
+    fn pool() -> Pool {
+        Pool {
+            pool_bump: 255,
+            index: 0,
+            creator: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            pool_base_token_account: Pubkey::new_unique(),
+            pool_quote_token_account: Pubkey::new_unique(),
+            lp_supply: 4_193_388_264_354,
+        }
+    }
+
+    fn mock_client(request: RpcRequest, result: Value) -> Client {
+        let mocks = Mocks::from([(request, result)]);
+        Client::new(RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks))
+    }
+
+    #[tokio::test]
+    async fn test_client_fetches_pool() {
+        let pool = pool();
+        let client = mock_client(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1 }, "value": program_account(&pool) }),
+        );
+
+        let fetched = client.pool(&Pubkey::new_unique()).await.unwrap();
+
+        assert_eq!(fetched.base_mint, pool.base_mint);
+        assert_eq!(fetched.quote_mint, pool.quote_mint);
+        assert_eq!(fetched.lp_supply, pool.lp_supply);
+    }
+
+    #[tokio::test]
+    async fn test_client_lists_pools() {
+        let (first, second) = (pool(), pool());
+        let (first_address, second_address) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let client = mock_client(
+            RpcRequest::GetProgramAccounts,
+            json!([
+                { "pubkey": first_address.to_string(), "account": program_account(&first) },
+                { "pubkey": second_address.to_string(), "account": program_account(&second) },
+            ]),
+        );
+
+        let pools = client.pools(&PoolFilter::default()).await.unwrap();
+
+        assert_eq!(pools.len(), 2);
+        assert_eq!(pools[0].0, first_address);
+        assert_eq!(pools[0].1.creator, first.creator);
+        assert_eq!(pools[1].0, second_address);
+        assert_eq!(pools[1].1.creator, second.creator);
+    }
+
+    #[test]
+    fn test_pool_filter_matches_pool_layout() {
+        let pool = pool();
+        let mut data = Vec::new();
+        pool.try_serialize(&mut data).unwrap();
+        let filter = PoolFilter {
+            base_mint: Some(pool.base_mint),
+            quote_mint: Some(pool.quote_mint),
+            creator: Some(pool.creator),
+        };
+
+        let filters = filter.rpc_filters();
+
+        assert_eq!(filters.len(), 4);
+        for filter in &filters {
+            let RpcFilterType::Memcmp(memcmp) = filter else {
+                panic!("Expected a memcmp filter, got {filter:?}");
+            };
+            assert!(memcmp.bytes_match(&data), "{memcmp:?} should match the pool");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_fetches_global_config() {
+        let config = GlobalConfig {
+            admin: Pubkey::new_unique(),
+            lp_fee_basis_points: 20,
+            protocol_fee_basis_points: 5,
+            disable_flags: 0b1_0000,
+            protocol_fee_recipients: [Pubkey::new_unique(); 8],
+        };
+        let client = mock_client(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1 }, "value": program_account(&config) }),
+        );
+
+        let fetched = client.global_config().await.unwrap();
+
+        assert_eq!(fetched.admin, config.admin);
+        assert_eq!(fetched.fees(), config.fees());
+        assert!(fetched.disabled().sell());
+        assert!(!fetched.disabled().buy());
+    }
+
+    #[tokio::test]
+    async fn test_client_rejects_missing_and_mismatched_accounts() {
+        let client = mock_client(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1 }, "value": null }),
+        );
+        let result = client.pool(&Pubkey::new_unique()).await;
+        assert!(matches!(result, Err(Error::AccountNotFound { .. })), "{result:?}");
+
+        let client = mock_client(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1 }, "value": program_account(&pool()) }),
+        );
+        let result = client.global_config().await;
+        assert!(matches!(result, Err(Error::DeserializeAccount { .. })), "{result:?}");
+    }
+
+    #[tokio::test]
+    async fn test_client_reads_reserves_from_pool_token_accounts() {
+        let (pool, pool_address) = (pool(), Pubkey::new_unique());
+        let client = mock_client(
+            RpcRequest::GetMultipleAccounts,
+            json!({
+                "context": { "slot": 1 },
+                "value": [
+                    token_account(&pool.base_mint, &pool_address, 51_069_729_277_308, &TOKEN_PROGRAM_ID),
+                    token_account(&pool.quote_mint, &pool_address, 656_973_136_556, &TOKEN_PROGRAM_ID),
+                ],
+            }),
+        );
+
+        let reserves = client.reserves(&pool).await.unwrap();
+
+        assert_eq!(reserves, Reserves { base: 51_069_729_277_308, quote: 656_973_136_556 });
+    }
+
+    #[tokio::test]
+    async fn test_client_reads_accounts_over_http() {
+        let pool = pool();
+        let (pool_address, missing, foreign) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let base_account = token_account(&pool.base_mint, &pool_address, 1_000, &TOKEN_PROGRAM_ID);
+        let quote_account =
+            token_account(&pool.quote_mint, &pool_address, 2_000, &TOKEN_2022_PROGRAM_ID);
+        let server = MockRpc::start(move |method, params| {
+            let address = params[0].as_str().unwrap_or_default().to_string();
+            Ok(match method {
+                "getAccountInfo" if address == pool_address.to_string() => {
+                    with_context(1, program_account(&pool))
+                }
+                // Same bytes as the pool, written by another program
+                "getAccountInfo" if address == foreign.to_string() => {
+                    let mut data = Vec::new();
+                    pool.try_serialize(&mut data).unwrap();
+                    with_context(1, ui_account(&data, &Pubkey::new_unique()))
+                }
+                "getAccountInfo" => with_context(1, Value::Null),
+                "getMultipleAccounts" => {
+                    with_context(1, json!([base_account.clone(), quote_account.clone()]))
+                }
+                method => panic!("Unexpected method {method}"),
+            })
+        })
+        .await;
+        let client = Client::new(server.client());
+
+        let fetched = client.pool(&pool_address).await.unwrap();
+        assert_eq!(fetched.pool_quote_token_account, pool.pool_quote_token_account);
+        let result = client.pool(&missing).await;
+        assert!(matches!(result, Err(Error::AccountNotFound { address }) if address == missing));
+        let result = client.pool(&foreign).await;
+        assert!(
+            matches!(result, Err(Error::UnexpectedOwner { address, .. }) if address == foreign)
+        );
+        let reserves = client.reserves(&fetched).await.unwrap();
+        assert_eq!(reserves, Reserves { base: 1_000, quote: 2_000 });
+
+        let requests = server.requests();
+        assert_eq!(server.methods()[..3], ["getAccountInfo"; 3]);
+        assert_eq!(requests[0]["params"][0], pool_address.to_string());
+        assert_eq!(requests[0]["params"][1]["encoding"], "base64+zstd");
+        assert_eq!(requests[0]["params"][1]["commitment"], "confirmed");
+        assert_eq!(requests[3]["method"], "getMultipleAccounts");
+        assert_eq!(
+            requests[3]["params"][0],
+            json!([
+                pool.pool_base_token_account.to_string(),
+                pool.pool_quote_token_account.to_string()
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_rejects_reserves_held_outside_token_programs() {
+        let pool = pool();
+        let owner = Pubkey::new_unique();
+        let server = MockRpc::start(move |_, _| {
+            let account = token_account(&pool.base_mint, &owner, 1_000, &TOKEN_PROGRAM_ID);
+            let mut forged = account.clone();
+            forged["owner"] = json!(Pubkey::new_unique().to_string());
+            Ok(with_context(1, json!([account, forged])))
+        })
+        .await;
+
+        let result = Client::new(server.client()).reserves(&pool).await;
+
+        assert!(
+            matches!(result, Err(Error::InvalidTokenAccount { address }) if address == pool.pool_quote_token_account),
+            "{result:?}"
+        );
+    }
+
+    /// Server of `pool` whose base and quote mints are owned by the given programs
+    async fn mint_server(pool: Pool, base_owner: Pubkey, quote_owner: Pubkey) -> MockRpc {
+        MockRpc::start(move |method, _| match method {
+            "getAccountInfo" => Ok(with_context(1, program_account(&pool))),
+            // Mints are requested in the order of the pool
+            "getMultipleAccounts" => Ok(with_context(
+                1,
+                json!([ui_account(&[0; 82], &base_owner), ui_account(&[0; 82], &quote_owner)]),
+            )),
+            _ => Err(json!({ "code": -32_601, "message": format!("{method} not found") })),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_client_reads_mint_token_programs() {
+        let address = Pubkey::new_unique();
+        let pool = pool();
+        let server = mint_server(pool, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID).await;
+
+        let accounts = Client::new(server.client()).pool_accounts(&address).await.unwrap();
+
+        assert_eq!(accounts.address, address);
+        assert_eq!(accounts.pool.lp_mint, pool.lp_mint);
+        assert_eq!(accounts.base_token_program, TOKEN_2022_PROGRAM_ID);
+        assert_eq!(accounts.quote_token_program, TOKEN_PROGRAM_ID);
+        let request = &server.requests()[1];
+        assert_eq!(request["method"], "getMultipleAccounts");
+        assert_eq!(
+            request["params"][0],
+            json!([pool.base_mint.to_string(), pool.quote_mint.to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_rejects_mints_outside_token_programs() {
+        let pool = pool();
+        let owner = Pubkey::new_unique();
+        let server = mint_server(pool, TOKEN_PROGRAM_ID, owner).await;
+
+        let result = Client::new(server.client()).pool_accounts(&Pubkey::new_unique()).await;
+
+        assert!(
+            matches!(result, Err(Error::UnexpectedMintOwner { address, owner: found }) if address == pool.quote_mint && found == owner),
+            "{result:?}"
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use anchor_client::anchor_lang::prelude::Pubkey;
+use chrono::{DateTime, SecondsFormat};
+use serde::{
+    ser::{Error as _, SerializeMap},
+    Deserialize, Serialize, Serializer,
+};
+
+use crate::{events, Event, EventRecord};
+
+/// How `u64` amounts are written by the serde representation of events
+///
+/// JSON consumers backed by `f64` numbers lose precision above 2^53, in which case amounts should
+/// be written as strings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AmountEncoding {
+    #[default]
+    Number,
+    String,
+}
+
+/// Serde representation of an [`Event`] with a given [`AmountEncoding`]
+#[derive(Clone, Copy, Debug)]
+pub struct SerializeEvent<'a> {
+    event: &'a Event,
+    amounts: AmountEncoding,
+}
+
+/// Serde representation of an [`EventRecord`] with a given [`AmountEncoding`]
+#[derive(Clone, Copy, Debug)]
+pub struct SerializeEventRecord<'a> {
+    record: &'a EventRecord,
+    amounts: AmountEncoding,
+}
+
+impl Event {
+    /// Serialize the event with its `type` tag, base58 pubkeys, RFC 3339 timestamps and amounts
+    /// written as `amounts`
+    #[must_use]
+    pub const fn serialize_with(&self, amounts: AmountEncoding) -> SerializeEvent<'_> {
+        SerializeEvent { event: self, amounts }
+    }
+}
+
+impl EventRecord {
+    /// Serialize the record, with its event written as by [`Event::serialize_with`]
+    #[must_use]
+    pub const fn serialize_with(&self, amounts: AmountEncoding) -> SerializeEventRecord<'_> {
+        SerializeEventRecord { record: self, amounts }
+    }
+}
+
+impl Serialize for Event {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with(AmountEncoding::default()).serialize(serializer)
+    }
+}
+
+impl Serialize for EventRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with(AmountEncoding::default()).serialize(serializer)
+    }
+}
+
+impl Serialize for SerializeEventRecord<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let EventRecord {
+            signature,
+            slot,
+            event_index,
+            invoker,
+            cpi_depth,
+            instruction_index,
+            tx_failed,
+            event,
+        } = self.record;
+
+        let mut map = serializer.serialize_map(Some(8))?;
+        map.serialize_entry("signature", signature)?;
+        map.serialize_entry("slot", slot)?;
+        map.serialize_entry("event_index", event_index)?;
+        map.serialize_entry("invoker", &invoker.as_ref().map(Base58))?;
+        map.serialize_entry("cpi_depth", cpi_depth)?;
+        map.serialize_entry("instruction_index", instruction_index)?;
+        map.serialize_entry("tx_failed", tx_failed)?;
+        map.serialize_entry("event", &event.serialize_with(self.amounts))?;
+        map.end()
+    }
+}
+
+/// A field of a generated event struct, written with the requested amount encoding
+struct Field<'a, T> {
+    value: &'a T,
+    amounts: AmountEncoding,
+}
+
+struct Base58<'a>(&'a Pubkey);
+
+impl Serialize for Base58<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self.0)
+    }
+}
+
+impl Serialize for Field<'_, u64> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.amounts {
+            AmountEncoding::Number => serializer.serialize_u64(*self.value),
+            AmountEncoding::String => serializer.collect_str(self.value),
+        }
+    }
+}
+
+/// The only signed integers of the IDL events are their unix `timestamp`s
+impl Serialize for Field<'_, i64> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let timestamp = DateTime::from_timestamp(*self.value, 0)
+            .ok_or_else(|| S::Error::custom(format!("timestamp out of range: {}", self.value)))?;
+        serializer.collect_str(&timestamp.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl Serialize for Field<'_, u16> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(*self.value)
+    }
+}
+
+impl Serialize for Field<'_, u8> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self.value)
+    }
+}
+
+impl Serialize for Field<'_, bool> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bool(*self.value)
+    }
+}
+
+impl Serialize for Field<'_, Pubkey> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Base58(self.value).serialize(serializer)
+    }
+}
+
+impl<const N: usize> Serialize for Field<'_, [Pubkey; N]> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.value.iter().map(Base58))
+    }
+}
+
+macro_rules! serialize_events {
+    ($($variant:ident($event:ident) { $($field:ident),* $(,)? }),* $(,)?) => {
+        impl Serialize for SerializeEvent<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let amounts = self.amounts;
+                let mut map = serializer.serialize_map(None)?;
+                match self.event {
+                    $(Event::$variant(event) => {
+                        // Destructure so that a field added to the IDL fails to compile here
+                        let events::$event { $($field),* } = event;
+                        map.serialize_entry("type", stringify!($variant))?;
+                        $(map.serialize_entry(stringify!($field), &Field { value: $field, amounts })?;)*
+                    })*
+                }
+                map.end()
+            }
+        }
+    };
+}
+
+serialize_events! {
+    Buy(BuyEvent) {
+        timestamp,
+        base_amount_out,
+        max_quote_amount_in,
+        user_base_token_reserves,
+        user_quote_token_reserves,
+        pool_base_token_reserves,
+        pool_quote_token_reserves,
+        quote_amount_in,
+        lp_fee_basis_points,
+        lp_fee,
+        protocol_fee_basis_points,
+        protocol_fee,
+        quote_amount_in_with_lp_fee,
+        user_quote_amount_in,
+        pool,
+        user,
+        user_base_token_account,
+        user_quote_token_account,
+        protocol_fee_recipient,
+        protocol_fee_recipient_token_account,
+    },
+    Sell(SellEvent) {
+        timestamp,
+        base_amount_in,
+        min_quote_amount_out,
+        user_base_token_reserves,
+        user_quote_token_reserves,
+        pool_base_token_reserves,
+        pool_quote_token_reserves,
+        quote_amount_out,
+        lp_fee_basis_points,
+        lp_fee,
+        protocol_fee_basis_points,
+        protocol_fee,
+        quote_amount_out_without_lp_fee,
+        user_quote_amount_out,
+        pool,
+        user,
+        user_base_token_account,
+        user_quote_token_account,
+        protocol_fee_recipient,
+        protocol_fee_recipient_token_account,
+    },
+    CreatePool(CreatePoolEvent) {
+        timestamp,
+        index,
+        creator,
+        base_mint,
+        quote_mint,
+        base_mint_decimals,
+        quote_mint_decimals,
+        base_amount_in,
+        quote_amount_in,
+        pool_base_amount,
+        pool_quote_amount,
+        minimum_liquidity,
+        initial_liquidity,
+        lp_token_amount_out,
+        pool_bump,
+        pool,
+        lp_mint,
+        user_base_token_account,
+        user_quote_token_account,
+    },
+    Deposit(DepositEvent) {
+        timestamp,
+        lp_token_amount_out,
+        max_base_amount_in,
+        max_quote_amount_in,
+        user_base_token_reserves,
+        user_quote_token_reserves,
+        pool_base_token_reserves,
+        pool_quote_token_reserves,
+        base_amount_in,
+        quote_amount_in,
+        lp_mint_supply,
+        pool,
+        user,
+        user_base_token_account,
+        user_quote_token_account,
+        user_pool_token_account,
+    },
+    Withdraw(WithdrawEvent) {
+        timestamp,
+        lp_token_amount_in,
+        min_base_amount_out,
+        min_quote_amount_out,
+        user_base_token_reserves,
+        user_quote_token_reserves,
+        pool_base_token_reserves,
+        pool_quote_token_reserves,
+        base_amount_out,
+        quote_amount_out,
+        lp_mint_supply,
+        pool,
+        user,
+        user_base_token_account,
+        user_quote_token_account,
+        user_pool_token_account,
+    },
+    CreateConfig(CreateConfigEvent) {
+        timestamp,
+        admin,
+        lp_fee_basis_points,
+        protocol_fee_basis_points,
+        protocol_fee_recipients,
+    },
+    Disable(DisableEvent) {
+        timestamp,
+        admin,
+        disable_create_pool,
+        disable_deposit,
+        disable_withdraw,
+        disable_buy,
+        disable_sell,
+    },
+    ExtendAccount(ExtendAccountEvent) {
+        timestamp,
+        account,
+        user,
+        current_size,
+        new_size,
+    },
+    UpdateAdmin(UpdateAdminEvent) {
+        timestamp,
+        admin,
+        new_admin,
+    },
+    UpdateFeeConfig(UpdateFeeConfigEvent) {
+        timestamp,
+        admin,
+        lp_fee_basis_points,
+        protocol_fee_basis_points,
+        protocol_fee_recipients,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_client::anchor_lang::prelude::pubkey;
+    use serde_json::json;
+
+    use super::*;
+
+    const POOL: Pubkey = pubkey!("D5FERBZNXA2KGMnCY9LMcPbHXadTCpHDtBQsGNcLzAky");
+    const USER: Pubkey = pubkey!("2QfBNK2WDwSLoUQRb1zAnp3KM12N9hQ8q6ApwUMnWW2T");
+
+    fn disable_event() -> Event {
+        Event::Disable(events::DisableEvent {
+            timestamp: 1_743_407_221,
+            admin: USER,
+            disable_buy: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_serialize_event() {
+        assert_eq!(
+            serde_json::to_value(disable_event()).unwrap(),
+            json!({
+                "type": "Disable",
+                "timestamp": "2025-03-31T07:47:01Z",
+                "admin": "2QfBNK2WDwSLoUQRb1zAnp3KM12N9hQ8q6ApwUMnWW2T",
+                "disable_create_pool": false,
+                "disable_deposit": false,
+                "disable_withdraw": false,
+                "disable_buy": true,
+                "disable_sell": false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialize_event_amount_encoding() {
+        let event = Event::Buy(events::BuyEvent {
+            base_amount_out: 32_556_578_918,
+            quote_amount_in: u64::MAX,
+            pool: POOL,
+            ..Default::default()
+        });
+
+        let numbers = serde_json::to_value(event.serialize_with(AmountEncoding::Number)).unwrap();
+        assert_eq!(numbers["type"], "Buy");
+        assert_eq!(numbers["base_amount_out"], json!(32_556_578_918_u64));
+        assert_eq!(numbers["quote_amount_in"], json!(u64::MAX));
+        assert_eq!(numbers["pool"], "D5FERBZNXA2KGMnCY9LMcPbHXadTCpHDtBQsGNcLzAky");
+
+        let strings = serde_json::to_value(event.serialize_with(AmountEncoding::String)).unwrap();
+        assert_eq!(strings["base_amount_out"], "32556578918");
+        assert_eq!(strings["quote_amount_in"], "18446744073709551615");
+        assert_eq!(strings["pool"], numbers["pool"]);
+    }
+
+    #[test]
+    fn test_serialize_event_pubkey_arrays() {
+        let event = Event::UpdateFeeConfig(events::UpdateFeeConfigEvent {
+            protocol_fee_recipients: [POOL; 8],
+            ..Default::default()
+        });
+
+        let value = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(value["protocol_fee_recipients"], json!(vec![POOL.to_string(); 8]));
+    }
+
+    #[test]
+    fn test_serialize_event_record() {
+        let record = EventRecord {
+            signature: "test_signature".to_string(),
+            slot: 330_012_345,
+            event_index: 0,
+            invoker: Some(POOL),
+            cpi_depth: 2,
+            instruction_index: 1,
+            tx_failed: false,
+            event: disable_event(),
+        };
+
+        let value = serde_json::to_value(record.serialize_with(AmountEncoding::String)).unwrap();
+
+        assert_eq!(value["signature"], "test_signature");
+        assert_eq!(value["slot"], 330_012_345);
+        assert_eq!(value["invoker"], "D5FERBZNXA2KGMnCY9LMcPbHXadTCpHDtBQsGNcLzAky");
+        assert_eq!(value["cpi_depth"], 2);
+        assert_eq!(value["tx_failed"], false);
+        assert_eq!(value["event"]["type"], "Disable");
+    }
+}
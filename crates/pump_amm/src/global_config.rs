@@ -0,0 +1,110 @@
+use anchor_client::anchor_lang::prelude::Pubkey;
+
+use crate::{pump_amm::accounts::GlobalConfig, quote::Fees};
+
+/// Instructions disabled by the admin through the `disable` instruction
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisableFlags(u8);
+
+impl DisableFlags {
+    const CREATE_POOL: u8 = 1 << 0;
+    const DEPOSIT: u8 = 1 << 1;
+    const WITHDRAW: u8 = 1 << 2;
+    const BUY: u8 = 1 << 3;
+    const SELL: u8 = 1 << 4;
+
+    #[must_use]
+    pub const fn new(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    #[must_use]
+    pub const fn create_pool(self) -> bool {
+        self.0 & Self::CREATE_POOL != 0
+    }
+
+    #[must_use]
+    pub const fn deposit(self) -> bool {
+        self.0 & Self::DEPOSIT != 0
+    }
+
+    #[must_use]
+    pub const fn withdraw(self) -> bool {
+        self.0 & Self::WITHDRAW != 0
+    }
+
+    #[must_use]
+    pub const fn buy(self) -> bool {
+        self.0 & Self::BUY != 0
+    }
+
+    #[must_use]
+    pub const fn sell(self) -> bool {
+        self.0 & Self::SELL != 0
+    }
+}
+
+impl GlobalConfig {
+    /// Fees charged on every trade
+    #[must_use]
+    pub const fn fees(&self) -> Fees {
+        Fees {
+            lp_fee_basis_points: self.lp_fee_basis_points,
+            protocol_fee_basis_points: self.protocol_fee_basis_points,
+        }
+    }
+
+    #[must_use]
+    pub const fn disabled(&self) -> DisableFlags {
+        DisableFlags::new(self.disable_flags)
+    }
+
+    /// First protocol fee recipient set by the admin, unset slots holding the default address
+    #[must_use]
+    pub fn protocol_fee_recipient(&self) -> Option<Pubkey> {
+        self.protocol_fee_recipients
+            .iter()
+            .copied()
+            .find(|recipient| *recipient != Pubkey::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disable_flags_follow_disable_instruction_arguments() {
+        let flags = DisableFlags::new(0b0_1010);
+
+        assert!(!flags.create_pool());
+        assert!(flags.deposit());
+        assert!(!flags.withdraw());
+        assert!(flags.buy());
+        assert!(!flags.sell());
+    }
+
+    #[test]
+    fn test_protocol_fee_recipient_skips_unset_slots() {
+        let recipient = Pubkey::new_unique();
+        let mut config = GlobalConfig {
+            admin: Pubkey::new_unique(),
+            lp_fee_basis_points: 20,
+            protocol_fee_basis_points: 5,
+            disable_flags: 0,
+            protocol_fee_recipients: [Pubkey::default(); 8],
+        };
+
+        let unset = config.protocol_fee_recipient();
+        config.protocol_fee_recipients[2] = recipient;
+        config.protocol_fee_recipients[5] = Pubkey::new_unique();
+
+        assert_eq!(unset, None);
+        assert_eq!(config.protocol_fee_recipient(), Some(recipient));
+    }
+}
@@ -0,0 +1,525 @@
+//! Builders for the `buy`, `sell`, `deposit` and `withdraw` instructions, filling in every
+//! account the program expects, and for the token instructions preparing the user's accounts
+
+use anchor_client::anchor_lang::{
+    prelude::{pubkey, Pubkey},
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        system_instruction,
+    },
+    system_program, InstructionData, ToAccountMetas,
+};
+
+use crate::{
+    pda,
+    pump_amm::{
+        accounts::Pool,
+        client::{accounts, args},
+    },
+    ID,
+};
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+/// Mint of wrapped SOL, whose token accounts hold their balance as lamports
+pub const NATIVE_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+/// `CreateIdempotent` of the associated token account program
+const CREATE_IDEMPOTENT: u8 = 1;
+/// `SyncNative` of the SPL Token program
+const SYNC_NATIVE: u8 = 17;
+
+/// Associated token account of `wallet` for `mint`, owned by `token_program`
+#[must_use]
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Create the associated token account of `wallet` for `mint`, paid by `payer`, unless it exists
+#[must_use]
+pub fn create_associated_token_account_idempotent_ix(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(wallet, mint, token_program), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![CREATE_IDEMPOTENT],
+    }
+}
+
+/// Wrap `lamports` of `wallet` into its existing wrapped SOL account of `token_program`
+#[must_use]
+pub fn wrap_sol_ixs(wallet: &Pubkey, lamports: u64, token_program: &Pubkey) -> [Instruction; 2] {
+    let account = associated_token_address(wallet, &NATIVE_MINT, token_program);
+    [
+        system_instruction::transfer(wallet, &account, lamports),
+        // The transferred lamports only count towards the token balance once synced
+        Instruction {
+            program_id: *token_program,
+            accounts: vec![AccountMeta::new(account, false)],
+            data: vec![SYNC_NATIVE],
+        },
+    ]
+}
+
+/// A pool together with its address and the token programs owning its mints
+///
+/// Both mints default to the legacy token program, [`Client::pool_accounts`] reads the actual
+/// ones. The user token accounts passed to the program are always the user's associated token
+/// accounts, created by [`PoolAccounts::buy_setup_ixs`] and [`PoolAccounts::sell_setup_ixs`].
+///
+/// [`Client::pool_accounts`]: crate::client::Client::pool_accounts
+#[derive(Clone, Copy, Debug)]
+pub struct PoolAccounts {
+    pub address: Pubkey,
+    pub pool: Pool,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+}
+
+impl PoolAccounts {
+    #[must_use]
+    pub const fn new(address: Pubkey, pool: Pool) -> Self {
+        Self {
+            address,
+            pool,
+            base_token_program: TOKEN_PROGRAM_ID,
+            quote_token_program: TOKEN_PROGRAM_ID,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_token_programs(self, base: Pubkey, quote: Pubkey) -> Self {
+        Self { base_token_program: base, quote_token_program: quote, ..self }
+    }
+
+    /// Buy exactly `base_amount_out`, paying at most `max_quote_amount_in` fees included
+    ///
+    /// `protocol_fee_recipient` must be one of `GlobalConfig::protocol_fee_recipients`.
+    #[must_use]
+    pub fn buy_ix(
+        &self,
+        user: &Pubkey,
+        protocol_fee_recipient: &Pubkey,
+        base_amount_out: u64,
+        max_quote_amount_in: u64,
+    ) -> Instruction {
+        let accounts = accounts::Buy {
+            pool: self.address,
+            user: *user,
+            global_config: pda::global_config_address(),
+            base_mint: self.pool.base_mint,
+            quote_mint: self.pool.quote_mint,
+            user_base_token_account: self.user_base_token_account(user),
+            user_quote_token_account: self.user_quote_token_account(user),
+            pool_base_token_account: self.pool.pool_base_token_account,
+            pool_quote_token_account: self.pool.pool_quote_token_account,
+            protocol_fee_recipient: *protocol_fee_recipient,
+            protocol_fee_recipient_token_account: self
+                .user_quote_token_account(protocol_fee_recipient),
+            base_token_program: self.base_token_program,
+            quote_token_program: self.quote_token_program,
+            system_program: system_program::ID,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            event_authority: pda::event_authority_address(),
+            program: ID,
+        };
+        instruction(&accounts, &args::Buy { base_amount_out, max_quote_amount_in })
+    }
+
+    /// Sell exactly `base_amount_in`, receiving at least `min_quote_amount_out` fees deducted
+    ///
+    /// `protocol_fee_recipient` must be one of `GlobalConfig::protocol_fee_recipients`.
+    #[must_use]
+    pub fn sell_ix(
+        &self,
+        user: &Pubkey,
+        protocol_fee_recipient: &Pubkey,
+        base_amount_in: u64,
+        min_quote_amount_out: u64,
+    ) -> Instruction {
+        let accounts = accounts::Sell {
+            pool: self.address,
+            user: *user,
+            global_config: pda::global_config_address(),
+            base_mint: self.pool.base_mint,
+            quote_mint: self.pool.quote_mint,
+            user_base_token_account: self.user_base_token_account(user),
+            user_quote_token_account: self.user_quote_token_account(user),
+            pool_base_token_account: self.pool.pool_base_token_account,
+            pool_quote_token_account: self.pool.pool_quote_token_account,
+            protocol_fee_recipient: *protocol_fee_recipient,
+            protocol_fee_recipient_token_account: self
+                .user_quote_token_account(protocol_fee_recipient),
+            base_token_program: self.base_token_program,
+            quote_token_program: self.quote_token_program,
+            system_program: system_program::ID,
+            associated_token_program: ASSOCIATED_TOKEN_PROGRAM_ID,
+            event_authority: pda::event_authority_address(),
+            program: ID,
+        };
+        instruction(&accounts, &args::Sell { base_amount_in, min_quote_amount_out })
+    }
+
+    /// Mint exactly `lp_token_amount_out`, depositing at most the given base and quote amounts
+    #[must_use]
+    pub fn deposit_ix(
+        &self,
+        user: &Pubkey,
+        lp_token_amount_out: u64,
+        max_base_amount_in: u64,
+        max_quote_amount_in: u64,
+    ) -> Instruction {
+        let accounts = accounts::Deposit {
+            pool: self.address,
+            global_config: pda::global_config_address(),
+            user: *user,
+            base_mint: self.pool.base_mint,
+            quote_mint: self.pool.quote_mint,
+            lp_mint: self.pool.lp_mint,
+            user_base_token_account: self.user_base_token_account(user),
+            user_quote_token_account: self.user_quote_token_account(user),
+            user_pool_token_account: self.user_pool_token_account(user),
+            pool_base_token_account: self.pool.pool_base_token_account,
+            pool_quote_token_account: self.pool.pool_quote_token_account,
+            token_program: TOKEN_PROGRAM_ID,
+            token_2022_program: TOKEN_2022_PROGRAM_ID,
+            event_authority: pda::event_authority_address(),
+            program: ID,
+        };
+        instruction(
+            &accounts,
+            &args::Deposit { lp_token_amount_out, max_base_amount_in, max_quote_amount_in },
+        )
+    }
+
+    /// Burn exactly `lp_token_amount_in`, withdrawing at least the given base and quote amounts
+    #[must_use]
+    pub fn withdraw_ix(
+        &self,
+        user: &Pubkey,
+        lp_token_amount_in: u64,
+        min_base_amount_out: u64,
+        min_quote_amount_out: u64,
+    ) -> Instruction {
+        let accounts = accounts::Withdraw {
+            pool: self.address,
+            global_config: pda::global_config_address(),
+            user: *user,
+            base_mint: self.pool.base_mint,
+            quote_mint: self.pool.quote_mint,
+            lp_mint: self.pool.lp_mint,
+            user_base_token_account: self.user_base_token_account(user),
+            user_quote_token_account: self.user_quote_token_account(user),
+            user_pool_token_account: self.user_pool_token_account(user),
+            pool_base_token_account: self.pool.pool_base_token_account,
+            pool_quote_token_account: self.pool.pool_quote_token_account,
+            token_program: TOKEN_PROGRAM_ID,
+            token_2022_program: TOKEN_2022_PROGRAM_ID,
+            event_authority: pda::event_authority_address(),
+            program: ID,
+        };
+        instruction(
+            &accounts,
+            &args::Withdraw { lp_token_amount_in, min_base_amount_out, min_quote_amount_out },
+        )
+    }
+
+    /// Instructions to run before [`PoolAccounts::buy_ix`]: create the user's base and quote
+    /// token accounts, and wrap `max_quote_amount_in` lamports when quoted in wrapped SOL
+    #[must_use]
+    pub fn buy_setup_ixs(&self, user: &Pubkey, max_quote_amount_in: u64) -> Vec<Instruction> {
+        let mut instructions = vec![
+            create_associated_token_account_idempotent_ix(
+                user,
+                user,
+                &self.pool.base_mint,
+                &self.base_token_program,
+            ),
+            create_associated_token_account_idempotent_ix(
+                user,
+                user,
+                &self.pool.quote_mint,
+                &self.quote_token_program,
+            ),
+        ];
+        if self.pool.quote_mint == NATIVE_MINT {
+            instructions.extend(wrap_sol_ixs(user, max_quote_amount_in, &self.quote_token_program));
+        }
+        instructions
+    }
+
+    /// Instructions to run before [`PoolAccounts::sell_ix`]: create the user's quote token
+    /// account receiving the proceeds
+    #[must_use]
+    pub fn sell_setup_ixs(&self, user: &Pubkey) -> Vec<Instruction> {
+        vec![create_associated_token_account_idempotent_ix(
+            user,
+            user,
+            &self.pool.quote_mint,
+            &self.quote_token_program,
+        )]
+    }
+
+    fn user_base_token_account(&self, user: &Pubkey) -> Pubkey {
+        associated_token_address(user, &self.pool.base_mint, &self.base_token_program)
+    }
+
+    fn user_quote_token_account(&self, user: &Pubkey) -> Pubkey {
+        associated_token_address(user, &self.pool.quote_mint, &self.quote_token_program)
+    }
+
+    /// LP mints are always created under Token-2022
+    fn user_pool_token_account(&self, user: &Pubkey) -> Pubkey {
+        associated_token_address(user, &self.pool.lp_mint, &TOKEN_2022_PROGRAM_ID)
+    }
+}
+
+fn instruction(accounts: &impl ToAccountMetas, args: &impl InstructionData) -> Instruction {
+    Instruction { program_id: ID, accounts: accounts.to_account_metas(None), data: args.data() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{events::BuyEvent, Event};
+
+    /// `BuyEvent` recorded on mainnet, as in `test_parse_logs_response_buy_event`
+    const BUY_EVENT: &str = "Z/RSHyz1d3d1SOpnAAAAAGb4hZQHAAAAia4KGQAAAAAAAAAAAAAAAITRnPLlBQAAfIUSmXIuAACsgqb2mAAAAOyx+hgAAAAAFAAAAAAAAAAWygwAAAAAAAUAAAAAAAAAhjIDAAAAAAACfAcZAAAAAIiuChkAAAAAs2KoY0K+8wW11Mzg7EPJF5YwXehmJXMsVZt7MEYcSPIU6zbh3Tj7uDLsYRbIHIH8Nxc2EsCB0aH03gYW/tnNAIr01Uaez9OsUAzDGS6qy0F7QYyFfp5dj72V3hxIbzYm0bqMzqITUWXmdMfp+z3UPNRElWs2FFc6IsqBgy9R1gFjg3MADqIssmTTSv9koEte+r+7dN3NBImXsZgVR9fREAe0ZyjFA6fIFZjsUWe5tjKg2nvc6Y8HxZZ7EO1veKHO";
+
+    fn buy_event() -> BuyEvent {
+        let Some(Event::Buy(event)) = Event::from_program_data(BUY_EVENT).unwrap() else {
+            panic!("Expected a Buy event");
+        };
+        event
+    }
+
+    /// The pool of the recorded buy, whose base mint the event does not record
+    fn pool_accounts(address: Pubkey) -> PoolAccounts {
+        PoolAccounts::new(
+            address,
+            Pool {
+                base_mint: Pubkey::new_unique(),
+                quote_mint: NATIVE_MINT,
+                lp_mint: Pubkey::new_unique(),
+                pool_base_token_account: Pubkey::new_unique(),
+                pool_quote_token_account: Pubkey::new_unique(),
+                ..Pool::default()
+            },
+        )
+    }
+
+    /// Check the accounts of `ix` against the `instruction` of the IDL: their number, flags,
+    /// fixed addresses and program derived addresses
+    fn assert_accounts_match_idl(ix: &Instruction, instruction: &str) {
+        let idl: serde_json::Value =
+            serde_json::from_str(include_str!("../idls/pump_amm.json")).unwrap();
+        let accounts = idl["instructions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|candidate| candidate["name"] == instruction)
+            .unwrap()["accounts"]
+            .as_array()
+            .unwrap();
+        assert_eq!(ix.accounts.len(), accounts.len(), "{instruction}");
+        let by_name: HashMap<_, _> = accounts
+            .iter()
+            .zip(&ix.accounts)
+            .map(|(account, meta)| (account["name"].as_str().unwrap(), meta.pubkey))
+            .collect();
+
+        for (account, meta) in accounts.iter().zip(&ix.accounts) {
+            let name = format!("{instruction}.{}", account["name"].as_str().unwrap());
+            let flag = |flag: &str| account[flag].as_bool().unwrap_or_default();
+            assert_eq!(meta.is_writable, flag("writable"), "{name}");
+            assert_eq!(meta.is_signer, flag("signer"), "{name}");
+            if let Some(address) = account["address"].as_str() {
+                assert_eq!(meta.pubkey.to_string(), address, "{name}");
+            }
+            let pda = &account["pda"];
+            if pda.is_null() {
+                continue;
+            }
+            let seeds: Vec<Vec<u8>> = pda["seeds"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|seed| match seed["kind"].as_str().unwrap() {
+                    "const" => serde_json::from_value(seed["value"].clone()).unwrap(),
+                    "account" => by_name[seed["path"].as_str().unwrap()].to_bytes().to_vec(),
+                    kind => panic!("Unexpected {kind} seed of {name}"),
+                })
+                .collect();
+            let program = if pda["program"].is_null() {
+                ID
+            } else {
+                let bytes: Vec<u8> =
+                    serde_json::from_value(pda["program"]["value"].clone()).unwrap();
+                Pubkey::try_from(bytes.as_slice()).unwrap()
+            };
+            let seeds: Vec<_> = seeds.iter().map(Vec::as_slice).collect();
+            assert_eq!(meta.pubkey, Pubkey::find_program_address(&seeds, &program).0, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_buy_ix_matches_recorded_buy() {
+        let event = buy_event();
+        let pool = pool_accounts(event.pool);
+
+        let ix = pool.buy_ix(
+            &event.user,
+            &event.protocol_fee_recipient,
+            event.base_amount_out,
+            event.max_quote_amount_in,
+        );
+
+        assert_eq!(ix.program_id, ID);
+        // Discriminator followed by `base_amount_out` and `max_quote_amount_in`
+        let mut data = vec![102, 6, 61, 18, 1, 218, 235, 234];
+        data.extend_from_slice(&32_556_578_918_u64.to_le_bytes());
+        data.extend_from_slice(&420_130_441_u64.to_le_bytes());
+        assert_eq!(ix.data, data);
+
+        assert_accounts_match_idl(&ix, "buy");
+        let keys: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(keys[0], event.pool);
+        assert_eq!(keys[1], event.user);
+        assert_eq!(keys[2], pubkey!("ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw"));
+        assert_eq!(keys[4], NATIVE_MINT);
+        assert_eq!(
+            keys[5],
+            associated_token_address(&event.user, &pool.pool.base_mint, &TOKEN_PROGRAM_ID)
+        );
+        assert_eq!(keys[6], event.user_quote_token_account);
+        assert_eq!(keys[9], event.protocol_fee_recipient);
+        assert_eq!(keys[10], event.protocol_fee_recipient_token_account);
+        assert_eq!(keys[16], ID);
+    }
+
+    #[test]
+    fn test_sell_ix_uses_user_associated_token_accounts() {
+        // Selling back on the pool of the recorded buy goes through the same token accounts
+        let event = buy_event();
+        let pool = pool_accounts(event.pool);
+
+        let ix = pool.sell_ix(&event.user, &event.protocol_fee_recipient, 1_000, 900);
+
+        assert_eq!(ix.data[..8], [51, 230, 133, 164, 1, 127, 131, 173]);
+        assert_accounts_match_idl(&ix, "sell");
+        assert_eq!(ix.accounts[0].pubkey, event.pool);
+        assert_eq!(
+            ix.accounts[5].pubkey,
+            associated_token_address(&event.user, &pool.pool.base_mint, &TOKEN_PROGRAM_ID)
+        );
+        assert_eq!(ix.accounts[6].pubkey, event.user_quote_token_account);
+        assert_eq!(ix.accounts[10].pubkey, event.protocol_fee_recipient_token_account);
+        assert_eq!(ix.accounts[11].pubkey, TOKEN_PROGRAM_ID);
+        assert_eq!(ix.accounts[12].pubkey, TOKEN_PROGRAM_ID);
+    }
+
+    #[test]
+    fn test_buy_setup_ixs_create_accounts_and_wrap_sol() {
+        let event = buy_event();
+        let pool =
+            pool_accounts(event.pool).with_token_programs(TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID);
+        let user_base_token_account =
+            associated_token_address(&event.user, &pool.pool.base_mint, &TOKEN_2022_PROGRAM_ID);
+
+        let instructions = pool.buy_setup_ixs(&event.user, event.max_quote_amount_in);
+
+        let [create_base, create_quote, transfer, sync_native] = instructions.as_slice() else {
+            panic!("Expected 4 instructions, got {instructions:?}");
+        };
+        for (ix, account, mint, token_program) in [
+            (create_base, user_base_token_account, pool.pool.base_mint, TOKEN_2022_PROGRAM_ID),
+            (create_quote, event.user_quote_token_account, NATIVE_MINT, TOKEN_PROGRAM_ID),
+        ] {
+            assert_eq!(ix.program_id, ASSOCIATED_TOKEN_PROGRAM_ID);
+            assert_eq!(ix.data, [1]);
+            assert_eq!(
+                ix.accounts,
+                [
+                    AccountMeta::new(event.user, true),
+                    AccountMeta::new(account, false),
+                    AccountMeta::new_readonly(event.user, false),
+                    AccountMeta::new_readonly(mint, false),
+                    AccountMeta::new_readonly(system_program::ID, false),
+                    AccountMeta::new_readonly(token_program, false),
+                ]
+            );
+        }
+        assert_eq!(
+            *transfer,
+            system_instruction::transfer(
+                &event.user,
+                &event.user_quote_token_account,
+                event.max_quote_amount_in
+            )
+        );
+        assert_eq!(sync_native.program_id, TOKEN_PROGRAM_ID);
+        assert_eq!(sync_native.data, [17]);
+        assert_eq!(sync_native.accounts, [AccountMeta::new(event.user_quote_token_account, false)]);
+        // The buy itself goes through the accounts created above
+        let buy = pool.buy_ix(&event.user, &event.protocol_fee_recipient, 1, 2);
+        assert_eq!(buy.accounts[5].pubkey, user_base_token_account);
+        assert_eq!(buy.accounts[6].pubkey, event.user_quote_token_account);
+    }
+
+    #[test]
+    fn test_setup_ixs_only_wrap_sol_for_buys_quoted_in_sol() {
+        let event = buy_event();
+        let mut pool = pool_accounts(event.pool);
+
+        let sell = pool.sell_setup_ixs(&event.user);
+        pool.pool.quote_mint = Pubkey::new_unique();
+        let buy = pool.buy_setup_ixs(&event.user, event.max_quote_amount_in);
+
+        assert_eq!(sell.len(), 1);
+        assert_eq!(sell[0].accounts[1].pubkey, event.user_quote_token_account);
+        assert_eq!(sell[0].accounts[3].pubkey, NATIVE_MINT);
+        assert_eq!(buy.len(), 2);
+        assert!(buy.iter().all(|ix| ix.program_id == ASSOCIATED_TOKEN_PROGRAM_ID));
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_ix_use_token_2022_lp_account() {
+        let event = buy_event();
+        let pool = pool_accounts(event.pool);
+        let lp_account =
+            associated_token_address(&event.user, &pool.pool.lp_mint, &TOKEN_2022_PROGRAM_ID);
+
+        let deposit = pool.deposit_ix(&event.user, 1_000, 10, 20);
+        let withdraw = pool.withdraw_ix(&event.user, 1_000, 10, 20);
+
+        assert_eq!(deposit.data[..8], [242, 35, 198, 137, 82, 225, 242, 182]);
+        assert_eq!(withdraw.data[..8], [183, 18, 70, 156, 148, 109, 161, 34]);
+        assert_accounts_match_idl(&deposit, "deposit");
+        assert_accounts_match_idl(&withdraw, "withdraw");
+        for ix in [deposit, withdraw] {
+            assert_eq!(ix.accounts[0].pubkey, event.pool);
+            assert_eq!(ix.accounts[2].pubkey, event.user);
+            assert_eq!(ix.accounts[7].pubkey, event.user_quote_token_account);
+            assert_eq!(ix.accounts[8].pubkey, lp_account);
+        }
+    }
+}
@@ -1,48 +1,257 @@
+mod client;
+mod encoding;
+mod global_config;
+pub mod instruction;
+mod logs;
+pub mod order;
+pub mod pda;
+mod pool_book;
+mod program_error;
+pub mod quote;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 use anchor_client::anchor_lang::prelude::*;
-use solana_client::rpc_response::{Response, RpcLogsResponse};
+use anchor_lang::Event as _;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use snafu::{ResultExt, Snafu};
+use solana_client::{
+    client_error::ClientError,
+    rpc_response::{Response, RpcLogsResponse},
+};
+use tracing::warn;
 
 declare_program!(pump_amm);
 
-use crate::pump_amm::events;
-pub use crate::pump_amm::ID;
+pub use crate::{
+    client::{Client, PoolFilter},
+    encoding::{AmountEncoding, SerializeEvent, SerializeEventRecord},
+    global_config::DisableFlags,
+    pool_book::{PoolBook, PoolState},
+    program_error::ProgramError,
+    pump_amm::{
+        accounts::{GlobalConfig, Pool},
+        ID,
+    },
+};
+use crate::{logs::LogStack, pump_amm::events};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to decode base64 program data: {}", source))]
+    DecodeProgramData { source: base64::DecodeError },
+
+    #[snafu(display("Failed to deserialize {}: {}", name, source))]
+    DeserializeEvent { name: &'static str, source: std::io::Error },
+
+    #[snafu(display("Failed to fetch account {}: {}", address, source))]
+    FetchAccount {
+        address: Pubkey,
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display("Failed to fetch program accounts: {}", source))]
+    FetchProgramAccounts {
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
 
-#[derive(Debug)]
+    #[snafu(display("Account {} not found", address))]
+    AccountNotFound { address: Pubkey },
+
+    #[snafu(display("Account {} is owned by {} rather than pump_amm", address, owner))]
+    UnexpectedOwner { address: Pubkey, owner: Pubkey },
+
+    #[snafu(display("Account {} is not a token account", address))]
+    InvalidTokenAccount { address: Pubkey },
+
+    #[snafu(display("Mint {} is owned by {} rather than a token program", address, owner))]
+    UnexpectedMintOwner { address: Pubkey, owner: Pubkey },
+
+    #[snafu(display("Failed to deserialize account {}: {}", address, source))]
+    DeserializeAccount {
+        address: Pubkey,
+        #[snafu(source(from(error::Error, Box::new)))]
+        source: Box<error::Error>,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Clone, Debug)]
 pub enum Event {
     Buy(events::BuyEvent),
     Sell(events::SellEvent),
     CreatePool(events::CreatePoolEvent),
     Deposit(events::DepositEvent),
     Withdraw(events::WithdrawEvent),
+    CreateConfig(events::CreateConfigEvent),
+    Disable(events::DisableEvent),
+    ExtendAccount(events::ExtendAccountEvent),
+    UpdateAdmin(events::UpdateAdminEvent),
+    UpdateFeeConfig(events::UpdateFeeConfigEvent),
 }
 
-#[must_use]
-pub fn parse_logs_response(logs: &Response<RpcLogsResponse>, program_id_str: &str) -> Vec<Event> {
-    let mut events: Vec<Event> = Vec::new();
-    for log in &logs.value.logs[..] {
-        if let Ok((Some(event), _, _)) =
-            anchor_client::handle_program_log::<events::BuyEvent>(program_id_str, log)
-        {
-            events.push(Event::Buy(event));
+/// An event together with the transaction and program frame that emitted it
+#[derive(Clone, Debug)]
+pub struct EventRecord {
+    pub signature: String,
+    pub slot: u64,
+    /// Position of the event among the `pump_amm` events of its transaction
+    pub event_index: usize,
+    /// Program that invoked `pump_amm`, `None` for a top-level instruction
+    pub invoker: Option<Pubkey>,
+    /// Depth of the emitting frame, 1 for a top-level instruction
+    pub cpi_depth: usize,
+    /// Index of the top-level instruction the event was emitted under
+    pub instruction_index: usize,
+    /// Whether the transaction failed, in which case none of its events took effect on-chain
+    pub tx_failed: bool,
+    pub event: Event,
+}
+
+type EventDecoder = fn(&[u8]) -> Result<Event>;
+
+macro_rules! event_decoders {
+    ($($variant:ident($event:ident)),* $(,)?) => {
+        [$((events::$event::DISCRIMINATOR, |data| {
+            decode::<events::$event>(data, stringify!($event)).map(Event::$variant)
+        }),)*]
+    };
+}
+
+/// Discriminator table of every event declared in the IDL
+const EVENT_DECODERS: [(&[u8], EventDecoder); 10] = event_decoders![
+    Buy(BuyEvent),
+    Sell(SellEvent),
+    CreatePool(CreatePoolEvent),
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+    CreateConfig(CreateConfigEvent),
+    Disable(DisableEvent),
+    ExtendAccount(ExtendAccountEvent),
+    UpdateAdmin(UpdateAdminEvent),
+    UpdateFeeConfig(UpdateFeeConfigEvent),
+];
+
+fn decode<T: AnchorDeserialize>(mut data: &[u8], name: &'static str) -> Result<T> {
+    T::deserialize(&mut data).context(DeserializeEventSnafu { name })
+}
+
+impl Event {
+    /// Decode an event from its raw bytes, an 8-byte discriminator followed by the borsh payload
+    ///
+    /// Returns `Ok(None)` if the discriminator does not belong to any `pump_amm` event.
+    ///
+    /// # Errors
+    /// Returns an error if the payload cannot be deserialized into the matching event
+    pub fn from_bytes(bytes: &[u8]) -> Result<Option<Self>> {
+        let Some((discriminator, data)) = bytes.split_at_checked(8) else {
+            return Ok(None);
+        };
+        EVENT_DECODERS
+            .iter()
+            .find(|(expected, _)| *expected == discriminator)
+            .map(|(_, decoder)| decoder(data))
+            .transpose()
+    }
+
+    /// Decode an event from the base64 payload of a `Program data:` log
+    ///
+    /// # Errors
+    /// Returns an error if the payload is not valid base64 or cannot be deserialized
+    pub fn from_program_data(program_data: &str) -> Result<Option<Self>> {
+        let bytes = STANDARD.decode(program_data).context(DecodeProgramDataSnafu)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Encode the event as emitted by the program, the inverse of [`Event::from_bytes`]
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Buy(event) => event.data(),
+            Self::Sell(event) => event.data(),
+            Self::CreatePool(event) => event.data(),
+            Self::Deposit(event) => event.data(),
+            Self::Withdraw(event) => event.data(),
+            Self::CreateConfig(event) => event.data(),
+            Self::Disable(event) => event.data(),
+            Self::ExtendAccount(event) => event.data(),
+            Self::UpdateAdmin(event) => event.data(),
+            Self::UpdateFeeConfig(event) => event.data(),
         }
-        if let Ok((Some(event), _, _)) =
-            anchor_client::handle_program_log::<events::SellEvent>(program_id_str, log)
-        {
-            events.push(Event::Sell(event));
+    }
+
+    /// Variant name, as written in the `type` field of the serialized event
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Buy(_) => "Buy",
+            Self::Sell(_) => "Sell",
+            Self::CreatePool(_) => "CreatePool",
+            Self::Deposit(_) => "Deposit",
+            Self::Withdraw(_) => "Withdraw",
+            Self::CreateConfig(_) => "CreateConfig",
+            Self::Disable(_) => "Disable",
+            Self::ExtendAccount(_) => "ExtendAccount",
+            Self::UpdateAdmin(_) => "UpdateAdmin",
+            Self::UpdateFeeConfig(_) => "UpdateFeeConfig",
         }
-        if let Ok((Some(event), _, _)) =
-            anchor_client::handle_program_log::<events::CreatePoolEvent>(program_id_str, log)
-        {
-            events.push(Event::CreatePool(event));
+    }
+
+    /// Unix timestamp of the block the event was emitted in, in seconds
+    #[must_use]
+    pub const fn timestamp(&self) -> i64 {
+        match self {
+            Self::Buy(event) => event.timestamp,
+            Self::Sell(event) => event.timestamp,
+            Self::CreatePool(event) => event.timestamp,
+            Self::Deposit(event) => event.timestamp,
+            Self::Withdraw(event) => event.timestamp,
+            Self::CreateConfig(event) => event.timestamp,
+            Self::Disable(event) => event.timestamp,
+            Self::ExtendAccount(event) => event.timestamp,
+            Self::UpdateAdmin(event) => event.timestamp,
+            Self::UpdateFeeConfig(event) => event.timestamp,
         }
-        if let Ok((Some(event), _, _)) =
-            anchor_client::handle_program_log::<events::DepositEvent>(program_id_str, log)
-        {
-            events.push(Event::Deposit(event));
+    }
+}
+
+/// Decode the events emitted by `program_id_str` from a transaction's logs
+///
+/// Logs are walked along their `invoke [n]` / `success` frames, so `Program data:` logs emitted by
+/// any other program are ignored even when it shares the CPI depth of `pump_amm`. Events of failed
+/// transactions are still returned, flagged with [`EventRecord::tx_failed`].
+#[must_use]
+pub fn parse_logs_response(
+    logs: &Response<RpcLogsResponse>,
+    program_id_str: &str,
+) -> Vec<EventRecord> {
+    let mut stack = LogStack::default();
+    let mut events: Vec<EventRecord> = Vec::new();
+    for log in &logs.value.logs[..] {
+        let Some(program_data) = stack.process(log) else {
+            continue;
+        };
+        if program_data.program_id != program_id_str {
+            continue;
         }
-        if let Ok((Some(event), _, _)) =
-            anchor_client::handle_program_log::<events::WithdrawEvent>(program_id_str, log)
-        {
-            events.push(Event::Withdraw(event));
+        match Event::from_program_data(program_data.data) {
+            Ok(Some(event)) => events.push(EventRecord {
+                signature: logs.value.signature.clone(),
+                slot: logs.context.slot,
+                event_index: events.len(),
+                invoker: program_data.invoker.and_then(|invoker| invoker.parse().ok()),
+                cpi_depth: program_data.cpi_depth,
+                instruction_index: program_data.instruction_index,
+                tx_failed: logs.value.err.is_some(),
+                event,
+            }),
+            Ok(None) => {}
+            Err(err) => {
+                warn!("Skipping undecodable program data in {}: {err}", logs.value.signature);
+            }
         }
     }
     events
@@ -51,8 +260,36 @@ pub fn parse_logs_response(logs: &Response<RpcLogsResponse>, program_id_str: &st
 #[cfg(test)]
 mod tests {
     use super::*;
+    use anchor_client::solana_sdk::transaction::TransactionError;
     use solana_client::rpc_response::{RpcLogsResponse, RpcResponseContext};
 
+    const PROGRAM_ID: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+
+    /// Build the logs of a top-level `pump_amm` instruction that emitted `event`
+    fn instruction_logs(instruction: &str, event: &impl anchor_lang::Event) -> Vec<String> {
+        vec![
+            format!("Program {PROGRAM_ID} invoke [1]"),
+            format!("Program log: Instruction: {instruction}"),
+            format!("Program data: {}", STANDARD.encode(event.data())),
+            format!("Program {PROGRAM_ID} consumed 24518 of 200000 compute units"),
+            format!("Program {PROGRAM_ID} success"),
+        ]
+    }
+
+    fn logs_response(logs: Vec<String>) -> Response<RpcLogsResponse> {
+        Response {
+            context: RpcResponseContext { slot: 0, api_version: None },
+            value: RpcLogsResponse { signature: "test_signature".to_string(), err: None, logs },
+        }
+    }
+
+    fn parse_single_event(instruction: &str, event: &impl anchor_lang::Event) -> Event {
+        let response = logs_response(instruction_logs(instruction, event));
+        let mut events = parse_logs_response(&response, PROGRAM_ID);
+        assert_eq!(events.len(), 1, "Expected exactly one event, got: {events:?}");
+        events.remove(0).event
+    }
+
     #[test]
     fn test_parse_logs_response_buy_event() {
         // Create a mock RPC logs response with the provided logs
@@ -116,7 +353,7 @@ mod tests {
         assert!(!events.is_empty(), "No events were parsed from the logs");
 
         // Check that the first event is a Buy event
-        match &events[0] {
+        match &events[0].event {
             Event::Buy(_) => {
                 // Test passed - we found a Buy event
                 println!("Successfully parsed a Buy event");
@@ -125,6 +362,12 @@ mod tests {
                 panic!("First event should be a Buy event, but got: {:?}", events[0]);
             }
         }
+
+        // Check that the event is attributed to the pump_amm frame nested under the router
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].invoker, Some(pubkey!("sattCHvHkM4XHLyadnU4KQtuNWZbWVDKzuPhmJBXCkq")));
+        assert_eq!(events[0].cpi_depth, 2);
+        assert_eq!(events[0].instruction_index, 2);
     }
 
     #[test]
@@ -144,4 +387,349 @@ mod tests {
         // Check that we have no events
         assert!(events.is_empty(), "Should not have parsed any events from empty logs");
     }
+
+    #[test]
+    fn test_parse_logs_response_sell_event() {
+        let fixture = events::SellEvent {
+            timestamp: 1_743_407_500,
+            base_amount_in: 32_556_578_918,
+            min_quote_amount_out: 400_000_000,
+            pool_base_token_reserves: 51_037_172_698_390,
+            pool_quote_token_reserves: 657_392_219_288,
+            quote_amount_out: 418_816_327,
+            lp_fee_basis_points: 20,
+            lp_fee: 837_633,
+            protocol_fee_basis_points: 5,
+            protocol_fee: 209_409,
+            quote_amount_out_without_lp_fee: 417_978_694,
+            user_quote_amount_out: 417_769_285,
+            pool: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            ..Default::default()
+        };
+
+        let Event::Sell(event) = parse_single_event("Sell", &fixture) else {
+            panic!("Expected a Sell event");
+        };
+        assert_eq!(event.base_amount_in, fixture.base_amount_in);
+        assert_eq!(event.user_quote_amount_out, fixture.user_quote_amount_out);
+        assert_eq!(event.pool, fixture.pool);
+        assert_eq!(event.user, fixture.user);
+    }
+
+    #[test]
+    fn test_parse_logs_response_create_pool_event() {
+        let fixture = events::CreatePoolEvent {
+            timestamp: 1_743_400_000,
+            index: 0,
+            creator: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            base_mint_decimals: 6,
+            quote_mint_decimals: 9,
+            base_amount_in: 206_900_000_000_000,
+            quote_amount_in: 84_990_359_123,
+            pool_base_amount: 206_900_000_000_000,
+            pool_quote_amount: 84_990_359_123,
+            minimum_liquidity: 100,
+            initial_liquidity: 4_193_388_375_914,
+            lp_token_amount_out: 4_193_388_375_814,
+            pool_bump: 254,
+            pool: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            ..Default::default()
+        };
+
+        let Event::CreatePool(event) = parse_single_event("CreatePool", &fixture) else {
+            panic!("Expected a CreatePool event");
+        };
+        assert_eq!(event.index, fixture.index);
+        assert_eq!(event.creator, fixture.creator);
+        assert_eq!(event.base_mint, fixture.base_mint);
+        assert_eq!(event.quote_mint, fixture.quote_mint);
+        assert_eq!(event.pool_bump, fixture.pool_bump);
+        assert_eq!(event.lp_token_amount_out, fixture.lp_token_amount_out);
+    }
+
+    #[test]
+    fn test_parse_logs_response_deposit_event() {
+        let fixture = events::DepositEvent {
+            timestamp: 1_743_401_000,
+            lp_token_amount_out: 1_000_000,
+            max_base_amount_in: 49_400_000,
+            max_quote_amount_in: 20_300_000,
+            pool_base_token_reserves: 206_900_000_000_000,
+            pool_quote_token_reserves: 84_990_359_123,
+            base_amount_in: 49_339_012,
+            quote_amount_in: 20_267_493,
+            lp_mint_supply: 4_193_388_375_914,
+            pool: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            ..Default::default()
+        };
+
+        let Event::Deposit(event) = parse_single_event("Deposit", &fixture) else {
+            panic!("Expected a Deposit event");
+        };
+        assert_eq!(event.lp_token_amount_out, fixture.lp_token_amount_out);
+        assert_eq!(event.base_amount_in, fixture.base_amount_in);
+        assert_eq!(event.quote_amount_in, fixture.quote_amount_in);
+        assert_eq!(event.lp_mint_supply, fixture.lp_mint_supply);
+        assert_eq!(event.pool, fixture.pool);
+    }
+
+    #[test]
+    fn test_parse_logs_response_withdraw_event() {
+        let fixture = events::WithdrawEvent {
+            timestamp: 1_743_402_000,
+            lp_token_amount_in: 1_000_000,
+            min_base_amount_out: 49_000_000,
+            min_quote_amount_out: 20_000_000,
+            pool_base_token_reserves: 206_900_049_339_012,
+            pool_quote_token_reserves: 84_990_379_390,
+            base_amount_out: 49_339_011,
+            quote_amount_out: 20_267_492,
+            lp_mint_supply: 4_193_389_375_914,
+            pool: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            ..Default::default()
+        };
+
+        let Event::Withdraw(event) = parse_single_event("Withdraw", &fixture) else {
+            panic!("Expected a Withdraw event");
+        };
+        assert_eq!(event.lp_token_amount_in, fixture.lp_token_amount_in);
+        assert_eq!(event.base_amount_out, fixture.base_amount_out);
+        assert_eq!(event.quote_amount_out, fixture.quote_amount_out);
+        assert_eq!(event.lp_mint_supply, fixture.lp_mint_supply);
+        assert_eq!(event.pool, fixture.pool);
+    }
+
+    #[test]
+    fn test_parse_logs_response_create_config_event() {
+        let fixture = events::CreateConfigEvent {
+            timestamp: 1_740_000_000,
+            admin: Pubkey::new_unique(),
+            lp_fee_basis_points: 20,
+            protocol_fee_basis_points: 5,
+            protocol_fee_recipients: [Pubkey::new_unique(); 8],
+        };
+
+        let Event::CreateConfig(event) = parse_single_event("CreateConfig", &fixture) else {
+            panic!("Expected a CreateConfig event");
+        };
+        assert_eq!(event.admin, fixture.admin);
+        assert_eq!(event.lp_fee_basis_points, fixture.lp_fee_basis_points);
+        assert_eq!(event.protocol_fee_basis_points, fixture.protocol_fee_basis_points);
+        assert_eq!(event.protocol_fee_recipients, fixture.protocol_fee_recipients);
+    }
+
+    #[test]
+    fn test_parse_logs_response_disable_event() {
+        let fixture = events::DisableEvent {
+            timestamp: 1_743_403_000,
+            admin: Pubkey::new_unique(),
+            disable_create_pool: false,
+            disable_deposit: false,
+            disable_withdraw: false,
+            disable_buy: true,
+            disable_sell: false,
+        };
+
+        let Event::Disable(event) = parse_single_event("Disable", &fixture) else {
+            panic!("Expected a Disable event");
+        };
+        assert_eq!(event.admin, fixture.admin);
+        assert!(event.disable_buy);
+        assert!(!event.disable_create_pool);
+        assert!(!event.disable_deposit);
+        assert!(!event.disable_withdraw);
+        assert!(!event.disable_sell);
+    }
+
+    #[test]
+    fn test_parse_logs_response_extend_account_event() {
+        let fixture = events::ExtendAccountEvent {
+            timestamp: 1_743_404_000,
+            account: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            current_size: 211,
+            new_size: 300,
+        };
+
+        let Event::ExtendAccount(event) = parse_single_event("ExtendAccount", &fixture) else {
+            panic!("Expected an ExtendAccount event");
+        };
+        assert_eq!(event.account, fixture.account);
+        assert_eq!(event.user, fixture.user);
+        assert_eq!(event.current_size, fixture.current_size);
+        assert_eq!(event.new_size, fixture.new_size);
+    }
+
+    #[test]
+    fn test_parse_logs_response_update_admin_event() {
+        let fixture = events::UpdateAdminEvent {
+            timestamp: 1_743_405_000,
+            admin: Pubkey::new_unique(),
+            new_admin: Pubkey::new_unique(),
+        };
+
+        let Event::UpdateAdmin(event) = parse_single_event("UpdateAdmin", &fixture) else {
+            panic!("Expected an UpdateAdmin event");
+        };
+        assert_eq!(event.admin, fixture.admin);
+        assert_eq!(event.new_admin, fixture.new_admin);
+    }
+
+    #[test]
+    fn test_parse_logs_response_update_fee_config_event() {
+        let fixture = events::UpdateFeeConfigEvent {
+            timestamp: 1_743_406_000,
+            admin: Pubkey::new_unique(),
+            lp_fee_basis_points: 25,
+            protocol_fee_basis_points: 10,
+            protocol_fee_recipients: [Pubkey::new_unique(); 8],
+        };
+
+        let Event::UpdateFeeConfig(event) = parse_single_event("UpdateFeeConfig", &fixture) else {
+            panic!("Expected an UpdateFeeConfig event");
+        };
+        assert_eq!(event.admin, fixture.admin);
+        assert_eq!(event.lp_fee_basis_points, fixture.lp_fee_basis_points);
+        assert_eq!(event.protocol_fee_basis_points, fixture.protocol_fee_basis_points);
+        assert_eq!(event.protocol_fee_recipients, fixture.protocol_fee_recipients);
+    }
+
+    #[test]
+    fn test_parse_logs_response_preserves_log_order() {
+        let withdraw = events::WithdrawEvent { lp_token_amount_in: 1, ..Default::default() };
+        let buy = events::BuyEvent { base_amount_out: 2, ..Default::default() };
+        let sell = events::SellEvent { base_amount_in: 3, ..Default::default() };
+        let logs = [
+            instruction_logs("Withdraw", &withdraw),
+            instruction_logs("Buy", &buy),
+            instruction_logs("Sell", &sell),
+        ]
+        .concat();
+
+        let events: Vec<Event> = parse_logs_response(&logs_response(logs), PROGRAM_ID)
+            .into_iter()
+            .map(|record| record.event)
+            .collect();
+
+        assert!(
+            matches!(
+                &events[..],
+                [Event::Withdraw(w), Event::Buy(b), Event::Sell(s)]
+                    if w.lp_token_amount_in == 1 && b.base_amount_out == 2 && s.base_amount_in == 3
+            ),
+            "Events should follow log order, got: {events:?}"
+        );
+    }
+
+    #[test]
+    fn test_parse_logs_response_skips_undecodable_program_data() {
+        let logs = vec![
+            format!("Program {PROGRAM_ID} invoke [1]"),
+            "Program data: not base64!".to_string(),
+            format!("Program data: {}", STANDARD.encode([0_u8; 16])),
+            format!(
+                "Program data: {}",
+                STANDARD.encode(&anchor_lang::Event::data(&events::BuyEvent::default())[..20])
+            ),
+            format!("Program {PROGRAM_ID} success"),
+        ];
+
+        let events = parse_logs_response(&logs_response(logs), PROGRAM_ID);
+
+        assert!(events.is_empty(), "Should not have parsed any events, got: {events:?}");
+    }
+
+    #[test]
+    fn test_event_from_bytes() {
+        assert!(matches!(Event::from_bytes(&[]), Ok(None)));
+        assert!(matches!(Event::from_bytes(&[0; 64]), Ok(None)));
+        assert!(matches!(
+            Event::from_bytes(events::DisableEvent::DISCRIMINATOR),
+            Err(Error::DeserializeEvent { name: "DisableEvent", .. })
+        ));
+        assert!(matches!(
+            Event::from_bytes(&anchor_lang::Event::data(&events::DisableEvent::default())),
+            Ok(Some(Event::Disable(_)))
+        ));
+    }
+
+    #[test]
+    fn test_event_to_bytes_round_trips() {
+        let event = Event::UpdateAdmin(events::UpdateAdminEvent {
+            timestamp: 1_743_407_221,
+            admin: Pubkey::new_unique(),
+            new_admin: Pubkey::new_unique(),
+        });
+
+        let Some(Event::UpdateAdmin(decoded)) = Event::from_bytes(&event.to_bytes()).unwrap()
+        else {
+            panic!("Should have decoded an UpdateAdmin event");
+        };
+        assert_eq!(Event::UpdateAdmin(decoded).timestamp(), 1_743_407_221);
+        assert_eq!(event.name(), "UpdateAdmin");
+    }
+
+    #[test]
+    fn test_parse_logs_response_rejects_program_data_from_other_programs() {
+        let buy = events::BuyEvent { base_amount_out: 1, ..Default::default() };
+        let logs = vec![
+            "Program sattCHvHkM4XHLyadnU4KQtuNWZbWVDKzuPhmJBXCkq invoke [1]".to_string(),
+            format!("Program {PROGRAM_ID} invoke [2]"),
+            format!("Program {PROGRAM_ID} success"),
+            "Program LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo invoke [2]".to_string(),
+            format!("Program data: {}", STANDARD.encode(anchor_lang::Event::data(&buy))),
+            "Program LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo success".to_string(),
+            "Program sattCHvHkM4XHLyadnU4KQtuNWZbWVDKzuPhmJBXCkq success".to_string(),
+        ];
+
+        let events = parse_logs_response(&logs_response(logs), PROGRAM_ID);
+
+        assert!(events.is_empty(), "Should not have attributed events to pump_amm: {events:?}");
+    }
+
+    #[test]
+    fn test_parse_logs_response_records_transaction_context() {
+        let deposit = events::DepositEvent { lp_token_amount_out: 1, ..Default::default() };
+        let withdraw = events::WithdrawEvent { lp_token_amount_in: 1, ..Default::default() };
+        let logs = [instruction_logs("Deposit", &deposit), instruction_logs("Withdraw", &withdraw)]
+            .concat();
+        let response = Response {
+            context: RpcResponseContext { slot: 330_012_345, api_version: None },
+            value: RpcLogsResponse { signature: "test_signature".to_string(), err: None, logs },
+        };
+
+        let events = parse_logs_response(&response, PROGRAM_ID);
+
+        assert_eq!(events.len(), 2);
+        for (index, record) in events.iter().enumerate() {
+            assert_eq!(record.signature, "test_signature");
+            assert_eq!(record.slot, 330_012_345);
+            assert_eq!(record.event_index, index);
+            assert_eq!(record.instruction_index, index);
+            assert_eq!(record.cpi_depth, 1);
+            assert_eq!(record.invoker, None);
+            assert!(!record.tx_failed);
+        }
+    }
+
+    #[test]
+    fn test_parse_logs_response_flags_failed_transactions() {
+        let sell = events::SellEvent { base_amount_in: 1, ..Default::default() };
+        let mut response = logs_response(instruction_logs("Sell", &sell));
+        response.value.err = Some(TransactionError::InstructionError(
+            0,
+            anchor_client::solana_sdk::instruction::InstructionError::Custom(6004),
+        ));
+
+        let events = parse_logs_response(&response, PROGRAM_ID);
+
+        assert_eq!(events.len(), 1);
+        assert!(events[0].tx_failed);
+    }
 }
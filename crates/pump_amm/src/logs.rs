@@ -0,0 +1,166 @@
+const PROGRAM: &str = "Program ";
+const PROGRAM_DATA: &str = "Program data: ";
+const LOG_TRUNCATED: &str = "Log truncated";
+
+/// A `Program data:` log together with the program frame that emitted it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramData<'a> {
+    pub program_id: &'a str,
+    pub invoker: Option<&'a str>,
+    pub cpi_depth: usize,
+    pub instruction_index: usize,
+    pub data: &'a str,
+}
+
+/// Tracks the `invoke [n]` / `success` / `failed` frames of a transaction's logs so that every
+/// `Program data:` log can be attributed to the program that emitted it
+#[derive(Debug, Default)]
+pub struct LogStack<'a> {
+    frames: Vec<&'a str>,
+    instruction_count: usize,
+    truncated: bool,
+}
+
+impl<'a> LogStack<'a> {
+    /// Feed the next log line, returning the program data it carries, if any
+    pub fn process(&mut self, log: &'a str) -> Option<ProgramData<'a>> {
+        if self.truncated {
+            return None;
+        }
+        if log == LOG_TRUNCATED {
+            // Nothing after this point can be attributed reliably
+            self.truncated = true;
+            return None;
+        }
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+            let (&program_id, parents) = self.frames.split_last()?;
+            return Some(ProgramData {
+                program_id,
+                invoker: parents.last().copied(),
+                cpi_depth: self.frames.len(),
+                instruction_index: self.instruction_count.checked_sub(1)?,
+                data,
+            });
+        }
+
+        let (program_id, action) = log.strip_prefix(PROGRAM)?.split_once(' ')?;
+        if let Some(depth) = Self::invoke_depth(action) {
+            // Resynchronize on the depth reported by the runtime rather than trusting the stack
+            self.frames.truncate(depth.saturating_sub(1));
+            if self.frames.is_empty() {
+                self.instruction_count += 1;
+            }
+            self.frames.push(program_id);
+        } else if action == "success" || action.starts_with("failed") {
+            if let Some(position) = self.frames.iter().rposition(|frame| *frame == program_id) {
+                self.frames.truncate(position);
+            }
+        }
+        None
+    }
+
+    fn invoke_depth(action: &str) -> Option<usize> {
+        action.strip_prefix("invoke [")?.strip_suffix(']')?.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUMP_AMM: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+    const ROUTER: &str = "sattCHvHkM4XHLyadnU4KQtuNWZbWVDKzuPhmJBXCkq";
+    const TOKEN: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+    fn program_data(logs: &[String]) -> Vec<ProgramData<'_>> {
+        let mut stack = LogStack::default();
+        logs.iter().filter_map(|log| stack.process(log)).collect()
+    }
+
+    #[test]
+    fn test_log_stack_attributes_nested_program_data() {
+        let logs = vec![
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]".to_string(),
+            "Program ComputeBudget111111111111111111111111111111 success".to_string(),
+            format!("Program {ROUTER} invoke [1]"),
+            format!("Program {PUMP_AMM} invoke [2]"),
+            "Program log: Instruction: Buy".to_string(),
+            format!("Program {TOKEN} invoke [3]"),
+            "Program log: Instruction: TransferChecked".to_string(),
+            format!("Program {TOKEN} consumed 6147 of 199168 compute units"),
+            format!("Program {TOKEN} success"),
+            "Program data: cHVtcA==".to_string(),
+            format!("Program {PUMP_AMM} success"),
+            "Program data: cm91dGVy".to_string(),
+            format!("Program {ROUTER} success"),
+        ];
+
+        assert_eq!(
+            program_data(&logs),
+            vec![
+                ProgramData {
+                    program_id: PUMP_AMM,
+                    invoker: Some(ROUTER),
+                    cpi_depth: 2,
+                    instruction_index: 1,
+                    data: "cHVtcA==",
+                },
+                ProgramData {
+                    program_id: ROUTER,
+                    invoker: None,
+                    cpi_depth: 1,
+                    instruction_index: 1,
+                    data: "cm91dGVy",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_stack_pops_failed_frames() {
+        let logs = vec![
+            format!("Program {ROUTER} invoke [1]"),
+            format!("Program {PUMP_AMM} invoke [2]"),
+            format!("Program {PUMP_AMM} failed: custom program error: 0x1774"),
+            "Program data: cm91dGVy".to_string(),
+            format!("Program {ROUTER} failed: custom program error: 0x1774"),
+        ];
+
+        let data = program_data(&logs);
+
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].program_id, ROUTER);
+        assert_eq!(data[0].cpi_depth, 1);
+    }
+
+    #[test]
+    fn test_log_stack_ignores_unattributable_program_data() {
+        let logs = vec![
+            "Program data: b3JwaGFu".to_string(),
+            format!("Program {PUMP_AMM} invoke [1]"),
+            "Log truncated".to_string(),
+            "Program data: cHVtcA==".to_string(),
+        ];
+
+        assert!(program_data(&logs).is_empty());
+    }
+
+    #[test]
+    fn test_log_stack_resynchronizes_on_invoke_depth() {
+        let logs = vec![
+            format!("Program {ROUTER} invoke [1]"),
+            format!("Program {TOKEN} invoke [2]"),
+            // The token program frame is never closed
+            format!("Program {PUMP_AMM} invoke [2]"),
+            "Program data: cHVtcA==".to_string(),
+        ];
+
+        let data = program_data(&logs);
+
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].program_id, PUMP_AMM);
+        assert_eq!(data[0].invoker, Some(ROUTER));
+        assert_eq!(data[0].cpi_depth, 2);
+        assert_eq!(data[0].instruction_index, 0);
+    }
+}
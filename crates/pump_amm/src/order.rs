@@ -0,0 +1,175 @@
+//! Orders ready to be passed to the `buy` / `sell` instructions, with limits derived from a quote
+//! and a slippage tolerance
+
+use crate::quote::{self, BuyQuote, Fees, Reserves, SellQuote};
+
+const BASIS_POINTS: u128 = 10_000;
+
+/// Tolerated price move between quoting and execution
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Slippage {
+    basis_points: u64,
+}
+
+impl Slippage {
+    #[must_use]
+    pub const fn from_basis_points(basis_points: u64) -> Self {
+        Self { basis_points }
+    }
+
+    #[must_use]
+    pub const fn from_percent(percent: u64) -> Self {
+        Self { basis_points: percent * 100 }
+    }
+
+    #[must_use]
+    pub const fn basis_points(self) -> u64 {
+        self.basis_points
+    }
+
+    /// `amount` increased by the tolerance, rounded up
+    fn above(self, amount: u64) -> quote::Result<u64> {
+        let amount = (u128::from(amount) * (BASIS_POINTS + u128::from(self.basis_points)))
+            .div_ceil(BASIS_POINTS);
+        u64::try_from(amount).map_err(|_| quote::Error::Overflow)
+    }
+
+    /// `amount` decreased by the tolerance, rounded down
+    fn below(self, amount: u64) -> u64 {
+        let kept = BASIS_POINTS.saturating_sub(u128::from(self.basis_points));
+        // Cannot exceed `amount`, which fits in a `u64`
+        u64::try_from(u128::from(amount) * kept / BASIS_POINTS).unwrap_or_default()
+    }
+}
+
+/// Arguments of a `buy` instruction together with the quote they were derived from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuyOrder {
+    pub base_amount_out: u64,
+    pub max_quote_amount_in: u64,
+    pub quote: BuyQuote,
+}
+
+/// Arguments of a `sell` instruction together with the quote they were derived from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SellOrder {
+    pub base_amount_in: u64,
+    pub min_quote_amount_out: u64,
+    pub quote: SellQuote,
+}
+
+/// Buy as many base tokens as `quote_amount_in` pays for at `reserves`, accepting to pay up to
+/// `slippage` more if the price moves before execution
+///
+/// # Errors
+/// Returns an error if `quote_amount_in` does not pay for a single base token, or the amounts
+/// overflow
+pub fn buy_order(
+    quote_amount_in: u64,
+    reserves: Reserves,
+    fees: Fees,
+    slippage: Slippage,
+) -> quote::Result<BuyOrder> {
+    let quote = quote::buy_quote_input(quote_amount_in, reserves, fees)?;
+    if quote.base_amount_out == 0 {
+        return Err(quote::Error::ZeroBaseAmountOut { quote_amount_in });
+    }
+    Ok(BuyOrder {
+        base_amount_out: quote.base_amount_out,
+        max_quote_amount_in: slippage.above(quote.user_quote_amount_in)?,
+        quote,
+    })
+}
+
+/// Buy exactly `base_amount_out` at `reserves`, accepting to pay up to `slippage` more if the
+/// price moves before execution
+///
+/// # Errors
+/// Returns an error if the pool does not hold more than `base_amount_out` or the amounts overflow
+pub fn buy_exact_order(
+    base_amount_out: u64,
+    reserves: Reserves,
+    fees: Fees,
+    slippage: Slippage,
+) -> quote::Result<BuyOrder> {
+    let quote = quote::buy_base_output(base_amount_out, reserves, fees)?;
+    Ok(BuyOrder {
+        base_amount_out,
+        max_quote_amount_in: slippage.above(quote.user_quote_amount_in)?,
+        quote,
+    })
+}
+
+/// Sell exactly `base_amount_in` at `reserves`, accepting to receive up to `slippage` less if the
+/// price moves before execution
+///
+/// # Errors
+/// Returns an error if the fees exceed the quote amount out or the amounts overflow
+pub fn sell_order(
+    base_amount_in: u64,
+    reserves: Reserves,
+    fees: Fees,
+    slippage: Slippage,
+) -> quote::Result<SellOrder> {
+    let quote = quote::sell_base_input(base_amount_in, reserves, fees)?;
+    Ok(SellOrder {
+        base_amount_in,
+        min_quote_amount_out: slippage.below(quote.user_quote_amount_out),
+        quote,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEES: Fees = Fees { lp_fee_basis_points: 20, protocol_fee_basis_points: 5 };
+
+    /// Reserves before the mainnet buy of `test_parse_logs_response_buy_event`
+    const RESERVES: Reserves = Reserves { base: 51_069_729_277_308, quote: 656_973_136_556 };
+
+    #[test]
+    fn test_buy_order_spends_quote_amount_with_slippage_headroom() {
+        let order = buy_order(420_130_440, RESERVES, FEES, Slippage::from_percent(3)).unwrap();
+
+        assert!(order.base_amount_out >= 32_556_578_918);
+        assert_eq!(order.quote.user_quote_amount_in, 420_130_440);
+        assert_eq!(order.max_quote_amount_in, 432_734_354);
+    }
+
+    #[test]
+    fn test_buy_order_rejects_amount_too_small_for_a_base_token() {
+        // Both fees are rounded up, so that even a single base token costs 3 quote tokens
+        assert_eq!(
+            buy_order(2, RESERVES, FEES, Slippage::from_percent(3)),
+            Err(quote::Error::ZeroBaseAmountOut { quote_amount_in: 2 })
+        );
+        assert!(buy_order(3, RESERVES, FEES, Slippage::default()).unwrap().base_amount_out > 0);
+    }
+
+    #[test]
+    fn test_buy_exact_order_without_slippage_matches_quote() {
+        let order = buy_exact_order(32_556_578_918, RESERVES, FEES, Slippage::default()).unwrap();
+
+        assert_eq!(order.max_quote_amount_in, order.quote.user_quote_amount_in);
+        assert_eq!(order.max_quote_amount_in, 420_130_440);
+    }
+
+    #[test]
+    fn test_sell_order_accepts_slippage_below_quote() {
+        let reserves = Reserves { base: 51_037_172_698_390, quote: 657_393_057_454 };
+
+        let order =
+            sell_order(32_556_578_918, reserves, FEES, Slippage::from_basis_points(100)).unwrap();
+
+        assert_eq!(order.quote.user_quote_amount_out, 418_035_557);
+        assert_eq!(order.min_quote_amount_out, 413_855_201);
+    }
+
+    #[test]
+    fn test_slippage_bounds() {
+        assert_eq!(Slippage::from_percent(100).below(1_000), 0);
+        assert_eq!(Slippage::from_percent(200).below(1_000), 0);
+        assert_eq!(Slippage::from_percent(1).above(u64::MAX), Err(quote::Error::Overflow));
+    }
+}
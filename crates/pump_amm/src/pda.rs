@@ -0,0 +1,143 @@
+//! Program derived addresses of the `pump_amm` program, derived from the seeds in its IDL
+
+use anchor_client::anchor_lang::prelude::Pubkey;
+
+use crate::{pump_amm::accounts::Pool, ID};
+
+pub const POOL_SEED: &[u8] = b"pool";
+pub const LP_MINT_SEED: &[u8] = b"pool_lp_mint";
+pub const GLOBAL_CONFIG_SEED: &[u8] = b"global_config";
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// Address and bump of the pool created by `creator` at `index` for a pair of mints
+#[must_use]
+pub fn pool_address(
+    index: u16,
+    creator: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            POOL_SEED,
+            &index.to_le_bytes(),
+            creator.as_ref(),
+            base_mint.as_ref(),
+            quote_mint.as_ref(),
+        ],
+        &ID,
+    )
+}
+
+/// Address and bump of the LP mint of `pool`
+#[must_use]
+pub fn lp_mint_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LP_MINT_SEED, pool.as_ref()], &ID)
+}
+
+/// Address of the `GlobalConfig` account
+#[must_use]
+pub fn global_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], &ID).0
+}
+
+/// Address of the authority signing the self-CPI that emits events
+#[must_use]
+pub fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &ID).0
+}
+
+impl Pool {
+    /// Address of the pool, re-derived with its recorded `pool_bump`
+    ///
+    /// Returns `None` if the seeds and bump do not form a valid program address.
+    #[must_use]
+    pub fn address(&self) -> Option<Pubkey> {
+        Pubkey::create_program_address(
+            &[
+                POOL_SEED,
+                &self.index.to_le_bytes(),
+                self.creator.as_ref(),
+                self.base_mint.as_ref(),
+                self.quote_mint.as_ref(),
+                &[self.pool_bump],
+            ],
+            &ID,
+        )
+        .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_client::anchor_lang::prelude::pubkey;
+
+    use super::*;
+
+    #[test]
+    fn test_singleton_addresses() {
+        assert_eq!(
+            global_config_address(),
+            pubkey!("ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw")
+        );
+        assert_eq!(
+            event_authority_address(),
+            pubkey!("GS4CU59F31iL7aR2Q8zVS8DRrcRnXX1yjQ66TqNVQnaR")
+        );
+    }
+
+    /// Seeds of `account` in the `create_pool` instruction of the IDL, resolved from `index`
+    /// and the given accounts
+    fn idl_seeds(account: &str, index: u16, accounts: &[(&str, Pubkey)]) -> Vec<Vec<u8>> {
+        let idl: serde_json::Value =
+            serde_json::from_str(include_str!("../idls/pump_amm.json")).unwrap();
+        let create_pool = idl["instructions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|instruction| instruction["name"] == "create_pool")
+            .unwrap();
+        let account = create_pool["accounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|candidate| candidate["name"] == account)
+            .unwrap();
+        account["pda"]["seeds"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|seed| match (seed["kind"].as_str().unwrap(), &seed["path"]) {
+                ("const", _) => serde_json::from_value(seed["value"].clone()).unwrap(),
+                ("arg", path) if path == "index" => index.to_le_bytes().to_vec(),
+                ("account", path) => {
+                    let (_, address) = accounts.iter().find(|(name, _)| path == name).unwrap();
+                    address.to_bytes().to_vec()
+                }
+                (kind, path) => panic!("Unexpected {kind} seed {path}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pool_and_lp_mint_addresses_follow_idl_seeds() {
+        let (creator, base_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let quote_mint = pubkey!("So11111111111111111111111111111111111111112");
+        // A non-zero index, so that its encoding is checked too
+        let index = 0x0102;
+        let accounts = [("creator", creator), ("base_mint", base_mint), ("quote_mint", quote_mint)];
+        let seeds = idl_seeds("pool", index, &accounts);
+        let seeds: Vec<_> = seeds.iter().map(Vec::as_slice).collect();
+        let (pool, pool_bump) = Pubkey::find_program_address(&seeds, &ID);
+
+        assert_eq!(pool_address(index, &creator, &base_mint, &quote_mint), (pool, pool_bump));
+        let seeds = idl_seeds("lp_mint", index, &[("pool", pool)]);
+        let seeds: Vec<_> = seeds.iter().map(Vec::as_slice).collect();
+        assert_eq!(lp_mint_address(&pool), Pubkey::find_program_address(&seeds, &ID));
+
+        let pool_account =
+            Pool { pool_bump, index, creator, base_mint, quote_mint, ..Pool::default() };
+        assert_eq!(pool_account.address(), Some(pool));
+        assert_ne!(Pool { index: 0, ..pool_account }.address(), Some(pool));
+    }
+}
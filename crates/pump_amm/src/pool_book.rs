@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+
+use anchor_client::anchor_lang::prelude::Pubkey;
+
+use crate::{pump_amm::accounts::Pool, quote::Reserves, Event, EventRecord};
+
+/// Last known state of a pool
+///
+/// Static fields are only known once the pool's `CreatePoolEvent` has been seen or the pool has
+/// been inserted from its account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolState {
+    pub address: Pubkey,
+    pub creator: Option<Pubkey>,
+    pub base_mint: Option<Pubkey>,
+    pub quote_mint: Option<Pubkey>,
+    pub lp_mint: Option<Pubkey>,
+    pub base_mint_decimals: Option<u8>,
+    pub quote_mint_decimals: Option<u8>,
+    pub reserves: Reserves,
+    pub lp_supply: Option<u64>,
+    /// Slot of the event or fetch the reserves were last updated from
+    pub slot: u64,
+}
+
+impl PoolState {
+    const fn new(address: Pubkey) -> Self {
+        Self {
+            address,
+            creator: None,
+            base_mint: None,
+            quote_mint: None,
+            lp_mint: None,
+            base_mint_decimals: None,
+            quote_mint_decimals: None,
+            reserves: Reserves { base: 0, quote: 0 },
+            lp_supply: None,
+            slot: 0,
+        }
+    }
+
+    /// Quote per base token in raw amounts, `None` while the pool holds no base tokens
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn price(&self) -> Option<f64> {
+        (self.reserves.base > 0).then(|| self.reserves.quote as f64 / self.reserves.base as f64)
+    }
+
+    /// Quote per base token adjusted for the mint decimals, `None` until they are known
+    #[must_use]
+    pub fn ui_price(&self) -> Option<f64> {
+        let decimals = i32::from(self.base_mint_decimals?) - i32::from(self.quote_mint_decimals?);
+        Some(self.price()? * 10_f64.powi(decimals))
+    }
+}
+
+/// Live state of the pools seen in the event stream
+///
+/// Events are applied in slot order: an event older than the last one applied to its pool only
+/// fills in the static fields it carries. Log notifications do not carry the position of their
+/// transaction within the slot, so transactions of a slot are applied in arrival order, while
+/// a transaction already applied is only followed by its later events. A pool fetched at a slot
+/// holds the state at the end of it, so no event of that slot is applied after it.
+#[derive(Clone, Debug, Default)]
+pub struct PoolBook {
+    pools: HashMap<Pubkey, PoolState>,
+    positions: HashMap<Pubkey, Position>,
+}
+
+/// Latest point of the event stream applied to a pool
+#[derive(Clone, Debug, Default)]
+struct Position {
+    slot: u64,
+    /// Whether the pool was fetched at the end of `slot`
+    fetched: bool,
+    /// Transactions applied within `slot`, in order, with the index of their last event applied
+    transactions: Vec<(String, usize)>,
+}
+
+impl Position {
+    /// Move to the event of `record`, returning `false` if it is not newer
+    fn advance(&mut self, record: &EventRecord) -> bool {
+        if record.slot < self.slot || (record.slot == self.slot && self.fetched) {
+            return false;
+        }
+        if record.slot > self.slot {
+            *self = Self { slot: record.slot, ..Self::default() };
+        }
+        let applied =
+            self.transactions.iter().position(|(signature, _)| *signature == record.signature);
+        match applied {
+            None => {
+                self.transactions.push((record.signature.clone(), record.event_index));
+                true
+            }
+            Some(index)
+                if index + 1 == self.transactions.len()
+                    && record.event_index > self.transactions[index].1 =>
+            {
+                self.transactions[index].1 = record.event_index;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Move to the end of `slot`, returning `false` if the stream is already past it
+    fn fetch(&mut self, slot: u64) -> bool {
+        if slot < self.slot {
+            return false;
+        }
+        *self = Self { slot, fetched: true, transactions: Vec::new() };
+        true
+    }
+}
+
+impl PoolBook {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seed the book with a pool fetched over RPC
+    pub fn insert_pool(&mut self, address: Pubkey, pool: &Pool, reserves: Reserves, slot: u64) {
+        let state = self.pools.entry(address).or_insert_with(|| PoolState::new(address));
+        state.creator = Some(pool.creator);
+        state.base_mint = Some(pool.base_mint);
+        state.quote_mint = Some(pool.quote_mint);
+        state.lp_mint = Some(pool.lp_mint);
+        if self.positions.entry(address).or_default().fetch(slot) {
+            state.reserves = reserves;
+            state.lp_supply = Some(pool.lp_supply);
+            state.slot = slot;
+        }
+    }
+
+    /// Apply the event of `record`, returning whether the state of its pool changed
+    ///
+    /// Events of failed transactions and events that do not concern a pool are ignored.
+    pub fn apply(&mut self, record: &EventRecord) -> bool {
+        if record.tx_failed {
+            return false;
+        }
+        let Some(address) = pool_address(&record.event) else {
+            return false;
+        };
+        let state = self.pools.entry(address).or_insert_with(|| PoolState::new(address));
+        let before = *state;
+
+        if let Event::CreatePool(event) = &record.event {
+            state.creator = Some(event.creator);
+            state.base_mint = Some(event.base_mint);
+            state.quote_mint = Some(event.quote_mint);
+            state.lp_mint = Some(event.lp_mint);
+            state.base_mint_decimals = Some(event.base_mint_decimals);
+            state.quote_mint_decimals = Some(event.quote_mint_decimals);
+        }
+        if self.positions.entry(address).or_default().advance(record) {
+            if let Some(reserves) = Reserves::after_event(&record.event) {
+                state.reserves = reserves;
+                state.slot = record.slot;
+            }
+            if let Some(lp_supply) = lp_supply_after(&record.event) {
+                state.lp_supply = Some(lp_supply);
+            }
+        }
+        *state != before
+    }
+
+    #[must_use]
+    pub fn get(&self, address: &Pubkey) -> Option<&PoolState> {
+        self.pools.get(address)
+    }
+
+    /// Pools trading `base_mint`, once their mints are known
+    pub fn by_base_mint<'a>(
+        &'a self,
+        base_mint: &'a Pubkey,
+    ) -> impl Iterator<Item = &'a PoolState> {
+        self.pools.values().filter(move |state| state.base_mint.as_ref() == Some(base_mint))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PoolState> {
+        self.pools.values()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pools.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+}
+
+const fn pool_address(event: &Event) -> Option<Pubkey> {
+    match event {
+        Event::Buy(event) => Some(event.pool),
+        Event::Sell(event) => Some(event.pool),
+        Event::CreatePool(event) => Some(event.pool),
+        Event::Deposit(event) => Some(event.pool),
+        Event::Withdraw(event) => Some(event.pool),
+        Event::CreateConfig(_)
+        | Event::Disable(_)
+        | Event::ExtendAccount(_)
+        | Event::UpdateAdmin(_)
+        | Event::UpdateFeeConfig(_) => None,
+    }
+}
+
+/// LP supply after the instruction, events recording the supply as it was before
+const fn lp_supply_after(event: &Event) -> Option<u64> {
+    match event {
+        Event::CreatePool(event) => Some(event.initial_liquidity),
+        Event::Deposit(event) => event.lp_mint_supply.checked_add(event.lp_token_amount_out),
+        Event::Withdraw(event) => event.lp_mint_supply.checked_sub(event.lp_token_amount_in),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{BuyEvent, CreatePoolEvent, DepositEvent, SellEvent};
+
+    fn record(slot: u64, event: Event) -> EventRecord {
+        EventRecord {
+            signature: format!("signature-{slot}"),
+            slot,
+            event_index: 0,
+            invoker: None,
+            cpi_depth: 1,
+            instruction_index: 0,
+            tx_failed: false,
+            event,
+        }
+    }
+
+    fn buy(pool: Pubkey) -> Event {
+        Event::Buy(BuyEvent {
+            base_amount_out: 100,
+            pool_base_token_reserves: 1_000,
+            pool_quote_token_reserves: 500,
+            quote_amount_in_with_lp_fee: 60,
+            pool,
+            ..BuyEvent::default()
+        })
+    }
+
+    fn sell(pool: Pubkey) -> Event {
+        Event::Sell(SellEvent {
+            base_amount_in: 100,
+            pool_base_token_reserves: 900,
+            pool_quote_token_reserves: 560,
+            quote_amount_out_without_lp_fee: 55,
+            pool,
+            ..SellEvent::default()
+        })
+    }
+
+    fn create_pool(pool: Pubkey) -> Event {
+        Event::CreatePool(CreatePoolEvent {
+            creator: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            base_mint_decimals: 6,
+            quote_mint_decimals: 9,
+            pool_base_amount: 1_000,
+            pool_quote_amount: 500,
+            initial_liquidity: 700,
+            pool,
+            ..CreatePoolEvent::default()
+        })
+    }
+
+    #[test]
+    fn test_pool_book_tracks_reserves_after_each_trade() {
+        let pool = Pubkey::new_unique();
+        let mut book = PoolBook::new();
+
+        assert!(book.apply(&record(10, create_pool(pool))));
+        assert!(book.apply(&record(11, buy(pool))));
+        assert_eq!(book.get(&pool).unwrap().reserves, Reserves { base: 900, quote: 560 });
+        assert!(book.apply(&record(12, sell(pool))));
+
+        let state = book.get(&pool).unwrap();
+        assert_eq!(state.reserves, Reserves { base: 1_000, quote: 505 });
+        assert_eq!(state.slot, 12);
+        assert_eq!(state.lp_supply, Some(700));
+        assert_eq!(state.price(), Some(0.505));
+        assert!((state.ui_price().unwrap() - 0.000_505).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_pool_book_ignores_stale_reserves() {
+        let pool = Pubkey::new_unique();
+        let mut book = PoolBook::new();
+
+        assert!(book.apply(&record(12, sell(pool))));
+        assert!(!book.apply(&record(11, buy(pool))));
+        assert_eq!(book.get(&pool).unwrap().reserves, Reserves { base: 1_000, quote: 505 });
+
+        // A late creation still fills in the static fields
+        assert!(book.apply(&record(10, create_pool(pool))));
+        let state = book.get(&pool).unwrap();
+        assert!(state.base_mint.is_some());
+        assert_eq!(state.reserves, Reserves { base: 1_000, quote: 505 });
+        assert_eq!(state.slot, 12);
+        assert_eq!(book.by_base_mint(&state.base_mint.unwrap()).count(), 1);
+    }
+
+    #[test]
+    fn test_pool_book_orders_events_within_a_slot() {
+        let pool = Pubkey::new_unique();
+        let mut book = PoolBook::new();
+        let mut first = record(11, buy(pool));
+        first.signature = "first".to_string();
+        let mut second = record(11, sell(pool));
+        second.signature = "second".to_string();
+
+        assert!(book.apply(&first));
+        assert!(book.apply(&second));
+        // Redelivering a transaction of the slot, as a backfill does, keeps the newer state
+        assert!(!book.apply(&first));
+        assert_eq!(book.get(&pool).unwrap().reserves, Reserves { base: 1_000, quote: 505 });
+        // A later event of the last transaction still applies
+        let mut later = record(11, buy(pool));
+        later.signature = "second".to_string();
+        later.event_index = 1;
+        assert!(book.apply(&later));
+        assert_eq!(book.get(&pool).unwrap().reserves, Reserves { base: 900, quote: 560 });
+
+        // A pool fetched at the slot holds its end state, which no event of the slot overrides
+        book.insert_pool(pool, &Pool::default(), Reserves { base: 10, quote: 20 }, 11);
+        let mut third = record(11, sell(pool));
+        third.signature = "third".to_string();
+        assert!(!book.apply(&third));
+        assert_eq!(book.get(&pool).unwrap().reserves, Reserves { base: 10, quote: 20 });
+        assert!(book.apply(&record(12, sell(pool))));
+        book.insert_pool(pool, &Pool::default(), Reserves { base: 10, quote: 20 }, 11);
+        assert_eq!(book.get(&pool).unwrap().reserves, Reserves { base: 1_000, quote: 505 });
+    }
+
+    #[test]
+    fn test_pool_book_ignores_failed_transactions_and_pool_less_events() {
+        let mut book = PoolBook::new();
+        let mut failed = record(1, buy(Pubkey::new_unique()));
+        failed.tx_failed = true;
+
+        assert!(!book.apply(&failed));
+        assert!(!book.apply(&record(1, Event::Disable(crate::events::DisableEvent::default()))));
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn test_pool_book_tracks_lp_supply() {
+        let pool = Pubkey::new_unique();
+        let mut book = PoolBook::new();
+        let deposit = Event::Deposit(DepositEvent {
+            lp_token_amount_out: 50,
+            pool_base_token_reserves: 1_000,
+            pool_quote_token_reserves: 500,
+            base_amount_in: 100,
+            quote_amount_in: 50,
+            lp_mint_supply: 700,
+            pool,
+            ..DepositEvent::default()
+        });
+
+        assert!(book.apply(&record(1, deposit)));
+
+        let state = book.get(&pool).unwrap();
+        assert_eq!(state.reserves, Reserves { base: 1_100, quote: 550 });
+        assert_eq!(state.lp_supply, Some(750));
+        assert_eq!(state.ui_price(), None);
+    }
+}
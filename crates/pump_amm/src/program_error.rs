@@ -0,0 +1,165 @@
+use std::{error, fmt};
+
+use anchor_client::solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use serde::Serialize;
+
+use crate::ID;
+
+const CUSTOM_PROGRAM_ERROR: &str = "failed: custom program error: 0x";
+
+macro_rules! program_errors {
+    ($($code:literal => $variant:ident: $message:literal),* $(,)?) => {
+        /// Custom error declared in the `pump_amm` IDL
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+        pub enum ProgramError {
+            $($variant,)*
+        }
+
+        impl ProgramError {
+            /// Look up the error by its code, `None` for codes outside of the IDL
+            #[must_use]
+            pub const fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            #[must_use]
+            pub const fn code(self) -> u32 {
+                match self {
+                    $(Self::$variant => $code,)*
+                }
+            }
+
+            /// Name of the error in the IDL, such as `ExceededSlippage`
+            #[must_use]
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => stringify!($variant),)*
+                }
+            }
+
+            #[must_use]
+            pub const fn message(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $message,)*
+                }
+            }
+        }
+    };
+}
+
+program_errors! {
+    6000 => FeeBasisPointsExceedsMaximum: "Fee basis points exceed the maximum",
+    6001 => ZeroBaseAmount: "Base amount is zero",
+    6002 => ZeroQuoteAmount: "Quote amount is zero",
+    6003 => TooLittlePoolTokenLiquidity: "Too little pool token liquidity",
+    6004 => ExceededSlippage: "Price moved beyond the slippage limit",
+    6005 => InvalidAdmin: "Signer is not the admin",
+    6006 => UnsupportedBaseMint: "Base mint is not supported",
+    6007 => UnsupportedQuoteMint: "Quote mint is not supported",
+    6008 => InvalidBaseMint: "Base mint does not match the pool",
+    6009 => InvalidQuoteMint: "Quote mint does not match the pool",
+    6010 => InvalidLpMint: "LP mint does not match the pool",
+    6011 => AllProtocolFeeRecipientsShouldBeNonZero: "All protocol fee recipients must be set",
+    6012 => UnsortedNotUniqueProtocolFeeRecipients: "Protocol fee recipients must be sorted and unique",
+    6013 => InvalidProtocolFeeRecipient: "Protocol fee recipient is not in the global config",
+    6014 => InvalidPoolBaseTokenAccount: "Pool base token account does not match the pool",
+    6015 => InvalidPoolQuoteTokenAccount: "Pool quote token account does not match the pool",
+    6016 => BuyMoreBaseAmountThanPoolReserves: "Buy amount exceeds the pool base reserves",
+    6017 => DisabledCreatePool: "Pool creation is disabled",
+    6018 => DisabledDeposit: "Deposits are disabled",
+    6019 => DisabledWithdraw: "Withdrawals are disabled",
+    6020 => DisabledBuy: "Buys are disabled",
+    6021 => DisabledSell: "Sells are disabled",
+    6022 => SameMint: "Base and quote mints are the same",
+    6023 => Overflow: "Arithmetic overflow",
+    6024 => Truncation: "Arithmetic truncation",
+    6025 => DivisionByZero: "Division by zero",
+    6026 => NewSizeLessThanCurrentSize: "New account size is less than the current size",
+    6027 => AccountTypeNotSupported: "Account type is not supported",
+}
+
+impl ProgramError {
+    /// Error of a transaction whose failing instruction is known to run `pump_amm`
+    ///
+    /// The custom code of an instruction that only reaches `pump_amm` through CPI may belong to
+    /// the calling program, use [`ProgramError::from_logs`] for arbitrary transactions.
+    #[must_use]
+    pub const fn from_transaction_error(err: &TransactionError) -> Option<Self> {
+        match err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+                Self::from_code(*code)
+            }
+            _ => None,
+        }
+    }
+
+    /// Error reported by the `pump_amm` frame that failed a transaction, from its logs
+    #[must_use]
+    pub fn from_logs<S: AsRef<str>>(logs: &[S]) -> Option<Self> {
+        let failed = format!("Program {ID} {CUSTOM_PROGRAM_ERROR}");
+        logs.iter().find_map(|log| {
+            let code = log.as_ref().strip_prefix(&failed)?;
+            Self::from_code(u32::from_str_radix(code, 16).ok()?)
+        })
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.name(), self.code(), self.message())
+    }
+}
+
+impl error::Error for ProgramError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_error_codes_match_idl() {
+        let idl: serde_json::Value =
+            serde_json::from_str(include_str!("../idls/pump_amm.json")).unwrap();
+        let errors = idl["errors"].as_array().unwrap();
+
+        assert_eq!(errors.len(), 28);
+        for error in errors {
+            let code = u32::try_from(error["code"].as_u64().unwrap()).unwrap();
+            let program_error = ProgramError::from_code(code).unwrap();
+            assert_eq!(program_error.name(), error["name"]);
+            assert_eq!(program_error.code(), code);
+        }
+        assert_eq!(ProgramError::from_code(6028), None);
+    }
+
+    #[test]
+    fn test_program_error_from_transaction_error() {
+        let err = TransactionError::InstructionError(2, InstructionError::Custom(6004));
+
+        assert_eq!(
+            ProgramError::from_transaction_error(&err),
+            Some(ProgramError::ExceededSlippage)
+        );
+        assert_eq!(ProgramError::from_transaction_error(&TransactionError::AccountNotFound), None);
+    }
+
+    #[test]
+    fn test_program_error_from_logs_ignores_other_programs() {
+        let logs = [
+            "Program sattCHvHkM4XHLyadnU4KQtuNWZbWVDKzuPhmJBXCkq invoke [1]",
+            "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA invoke [2]",
+            "Program log: Instruction: Sell",
+            "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA failed: custom program error: 0x1785",
+            "Program sattCHvHkM4XHLyadnU4KQtuNWZbWVDKzuPhmJBXCkq failed: custom program error: 0x1770",
+        ];
+
+        let error = ProgramError::from_logs(&logs).unwrap();
+
+        assert_eq!(error, ProgramError::DisabledSell);
+        assert_eq!(error.to_string(), "DisabledSell (6021): Sells are disabled");
+        assert_eq!(ProgramError::from_logs(&logs[4..]), None);
+    }
+}
@@ -0,0 +1,374 @@
+//! Constant-product quotes reproducing the `buy` / `sell` math of the `pump_amm` program
+//!
+//! Amounts paid to the pool are rounded up and amounts paid out are rounded down, fees being
+//! rounded up, so that quotes match the amounts recorded in `BuyEvent` / `SellEvent` exactly.
+
+use snafu::{ensure, OptionExt, Snafu};
+
+use crate::{
+    events::{BuyEvent, SellEvent},
+    Event,
+};
+
+const BASIS_POINTS: u128 = 10_000;
+
+#[derive(Debug, PartialEq, Eq, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Cannot buy {} base tokens out of a pool holding {}",
+        base_amount_out,
+        pool_base_token_reserves
+    ))]
+    BuyMoreBaseAmountThanPoolReserves { base_amount_out: u64, pool_base_token_reserves: u64 },
+
+    #[snafu(display("{} quote tokens do not pay for a single base token", quote_amount_in))]
+    ZeroBaseAmountOut { quote_amount_in: u64 },
+
+    #[snafu(display("Arithmetic overflow"))]
+    Overflow,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Token reserves of a pool, as held by its base and quote token accounts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reserves {
+    pub base: u64,
+    pub quote: u64,
+}
+
+/// Fees charged on the quote side of every trade, as configured by `GlobalConfig`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fees {
+    pub lp_fee_basis_points: u64,
+    pub protocol_fee_basis_points: u64,
+}
+
+/// Amounts of a buy, named after the fields of `BuyEvent`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuyQuote {
+    pub base_amount_out: u64,
+    /// Quote amount moved along the curve, before fees
+    pub quote_amount_in: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub quote_amount_in_with_lp_fee: u64,
+    /// Quote amount paid by the user, fees included
+    pub user_quote_amount_in: u64,
+}
+
+/// Amounts of a sell, named after the fields of `SellEvent`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SellQuote {
+    pub base_amount_in: u64,
+    /// Quote amount moved along the curve, before fees
+    pub quote_amount_out: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub quote_amount_out_without_lp_fee: u64,
+    /// Quote amount received by the user, fees deducted
+    pub user_quote_amount_out: u64,
+}
+
+impl Reserves {
+    /// Reserves after `quote` has been executed, the LP fee staying in the pool
+    ///
+    /// # Errors
+    /// Returns an error if the reserves overflow
+    pub fn after_buy(self, quote: &BuyQuote) -> Result<Self> {
+        Ok(Self {
+            base: self.base.checked_sub(quote.base_amount_out).context(OverflowSnafu)?,
+            quote: self
+                .quote
+                .checked_add(quote.quote_amount_in_with_lp_fee)
+                .context(OverflowSnafu)?,
+        })
+    }
+
+    /// Reserves after `quote` has been executed, the LP fee staying in the pool
+    ///
+    /// # Errors
+    /// Returns an error if the reserves overflow
+    pub fn after_sell(self, quote: &SellQuote) -> Result<Self> {
+        Ok(Self {
+            base: self.base.checked_add(quote.base_amount_in).context(OverflowSnafu)?,
+            quote: self
+                .quote
+                .checked_sub(quote.quote_amount_out_without_lp_fee)
+                .context(OverflowSnafu)?,
+        })
+    }
+
+    /// Reserves of the pool right after the instruction recorded by `event`
+    ///
+    /// Events record the reserves as they were before the instruction, so the amounts it moved
+    /// are applied to them. Returns `None` for events that do not record reserves, and for
+    /// inconsistent amounts.
+    #[must_use]
+    pub fn after_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::Buy(event) => Self::after_buy_event(event),
+            Event::Sell(event) => Self::after_sell_event(event),
+            Event::Deposit(event) => Some(Self {
+                base: event.pool_base_token_reserves.checked_add(event.base_amount_in)?,
+                quote: event.pool_quote_token_reserves.checked_add(event.quote_amount_in)?,
+            }),
+            Event::Withdraw(event) => Some(Self {
+                base: event.pool_base_token_reserves.checked_sub(event.base_amount_out)?,
+                quote: event.pool_quote_token_reserves.checked_sub(event.quote_amount_out)?,
+            }),
+            Event::CreatePool(event) => {
+                Some(Self { base: event.pool_base_amount, quote: event.pool_quote_amount })
+            }
+            _ => None,
+        }
+    }
+
+    fn after_buy_event(event: &BuyEvent) -> Option<Self> {
+        Some(Self {
+            base: event.pool_base_token_reserves.checked_sub(event.base_amount_out)?,
+            quote: event
+                .pool_quote_token_reserves
+                .checked_add(event.quote_amount_in_with_lp_fee)?,
+        })
+    }
+
+    fn after_sell_event(event: &SellEvent) -> Option<Self> {
+        Some(Self {
+            base: event.pool_base_token_reserves.checked_add(event.base_amount_in)?,
+            quote: event
+                .pool_quote_token_reserves
+                .checked_sub(event.quote_amount_out_without_lp_fee)?,
+        })
+    }
+}
+
+impl Fees {
+    fn lp_fee(self, amount: u64) -> Result<u64> {
+        fee(amount, self.lp_fee_basis_points)
+    }
+
+    fn protocol_fee(self, amount: u64) -> Result<u64> {
+        fee(amount, self.protocol_fee_basis_points)
+    }
+}
+
+/// Quote buying exactly `base_amount_out`, as done by the `buy` instruction
+///
+/// # Errors
+/// Returns an error if the pool does not hold more than `base_amount_out` or the amounts overflow
+pub fn buy_base_output(base_amount_out: u64, reserves: Reserves, fees: Fees) -> Result<BuyQuote> {
+    ensure!(
+        base_amount_out < reserves.base,
+        BuyMoreBaseAmountThanPoolReservesSnafu {
+            base_amount_out,
+            pool_base_token_reserves: reserves.base,
+        }
+    );
+    let quote_amount_in = to_u64(
+        (u128::from(reserves.quote) * u128::from(base_amount_out))
+            .div_ceil(u128::from(reserves.base - base_amount_out)),
+    )?;
+    let lp_fee = fees.lp_fee(quote_amount_in)?;
+    let protocol_fee = fees.protocol_fee(quote_amount_in)?;
+    let quote_amount_in_with_lp_fee = quote_amount_in.checked_add(lp_fee).context(OverflowSnafu)?;
+    let user_quote_amount_in =
+        quote_amount_in_with_lp_fee.checked_add(protocol_fee).context(OverflowSnafu)?;
+    Ok(BuyQuote {
+        base_amount_out,
+        quote_amount_in,
+        lp_fee,
+        protocol_fee,
+        quote_amount_in_with_lp_fee,
+        user_quote_amount_in,
+    })
+}
+
+/// Quote the largest buy whose `user_quote_amount_in` does not exceed `max_quote_amount_in`
+///
+/// The program only buys exact base amounts, so this searches the `base_amount_out` to pass to
+/// [`buy_base_output`].
+///
+/// # Errors
+/// Returns an error if the pool holds no base tokens
+pub fn buy_quote_input(
+    max_quote_amount_in: u64,
+    reserves: Reserves,
+    fees: Fees,
+) -> Result<BuyQuote> {
+    // The cost grows with the base amount, though several base amounts may cost the same once
+    // rounded
+    let (mut low, mut high) = (0, reserves.base.saturating_sub(1));
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        match buy_base_output(mid, reserves, fees) {
+            Ok(quote) if quote.user_quote_amount_in <= max_quote_amount_in => low = mid,
+            Ok(_) | Err(Error::Overflow) => high = mid - 1,
+            Err(err) => return Err(err),
+        }
+    }
+    buy_base_output(low, reserves, fees)
+}
+
+/// Quote selling exactly `base_amount_in`, as done by the `sell` instruction
+///
+/// # Errors
+/// Returns an error if the fees exceed the quote amount out or the amounts overflow
+pub fn sell_base_input(base_amount_in: u64, reserves: Reserves, fees: Fees) -> Result<SellQuote> {
+    let denominator = u128::from(reserves.base) + u128::from(base_amount_in);
+    ensure!(denominator > 0, OverflowSnafu);
+    let quote_amount_out =
+        to_u64(u128::from(reserves.quote) * u128::from(base_amount_in) / denominator)?;
+    let lp_fee = fees.lp_fee(quote_amount_out)?;
+    let protocol_fee = fees.protocol_fee(quote_amount_out)?;
+    let quote_amount_out_without_lp_fee =
+        quote_amount_out.checked_sub(lp_fee).context(OverflowSnafu)?;
+    let user_quote_amount_out =
+        quote_amount_out_without_lp_fee.checked_sub(protocol_fee).context(OverflowSnafu)?;
+    Ok(SellQuote {
+        base_amount_in,
+        quote_amount_out,
+        lp_fee,
+        protocol_fee,
+        quote_amount_out_without_lp_fee,
+        user_quote_amount_out,
+    })
+}
+
+fn fee(amount: u64, basis_points: u64) -> Result<u64> {
+    to_u64((u128::from(amount) * u128::from(basis_points)).div_ceil(BASIS_POINTS))
+}
+
+fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).ok().context(OverflowSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `BuyEvent` recorded on mainnet, as in `test_parse_logs_response_buy_event`
+    const BUY_EVENT: &str = "Z/RSHyz1d3d1SOpnAAAAAGb4hZQHAAAAia4KGQAAAAAAAAAAAAAAAITRnPLlBQAAfIUSmXIuAACsgqb2mAAAAOyx+hgAAAAAFAAAAAAAAAAWygwAAAAAAAUAAAAAAAAAhjIDAAAAAAACfAcZAAAAAIiuChkAAAAAs2KoY0K+8wW11Mzg7EPJF5YwXehmJXMsVZt7MEYcSPIU6zbh3Tj7uDLsYRbIHIH8Nxc2EsCB0aH03gYW/tnNAIr01Uaez9OsUAzDGS6qy0F7QYyFfp5dj72V3hxIbzYm0bqMzqITUWXmdMfp+z3UPNRElWs2FFc6IsqBgy9R1gFjg3MADqIssmTTSv9koEte+r+7dN3NBImXsZgVR9fREAe0ZyjFA6fIFZjsUWe5tjKg2nvc6Y8HxZZ7EO1veKHO";
+
+    const FEES: Fees = Fees { lp_fee_basis_points: 20, protocol_fee_basis_points: 5 };
+
+    fn buy_event() -> BuyEvent {
+        let Some(Event::Buy(event)) = Event::from_program_data(BUY_EVENT).unwrap() else {
+            panic!("Expected a Buy event");
+        };
+        event
+    }
+
+    #[test]
+    fn test_buy_base_output_matches_recorded_event() {
+        let event = buy_event();
+        let reserves = Reserves {
+            base: event.pool_base_token_reserves,
+            quote: event.pool_quote_token_reserves,
+        };
+        let fees = Fees {
+            lp_fee_basis_points: event.lp_fee_basis_points,
+            protocol_fee_basis_points: event.protocol_fee_basis_points,
+        };
+
+        let quote = buy_base_output(event.base_amount_out, reserves, fees).unwrap();
+
+        assert_eq!(
+            quote,
+            BuyQuote {
+                base_amount_out: event.base_amount_out,
+                quote_amount_in: event.quote_amount_in,
+                lp_fee: event.lp_fee,
+                protocol_fee: event.protocol_fee,
+                quote_amount_in_with_lp_fee: event.quote_amount_in_with_lp_fee,
+                user_quote_amount_in: event.user_quote_amount_in,
+            }
+        );
+        assert!(quote.user_quote_amount_in <= event.max_quote_amount_in);
+    }
+
+    #[test]
+    fn test_buy_quote_input_spends_at_most_the_given_amount() {
+        let event = buy_event();
+        let reserves = Reserves {
+            base: event.pool_base_token_reserves,
+            quote: event.pool_quote_token_reserves,
+        };
+
+        let quote = buy_quote_input(event.user_quote_amount_in, reserves, FEES).unwrap();
+
+        assert!(quote.base_amount_out >= event.base_amount_out);
+        assert_eq!(quote.user_quote_amount_in, event.user_quote_amount_in);
+        let next = buy_base_output(quote.base_amount_out + 1, reserves, FEES).unwrap();
+        assert!(next.user_quote_amount_in > event.user_quote_amount_in);
+    }
+
+    #[test]
+    fn test_sell_base_input_charges_the_recorded_fees() {
+        // The program charges the same fees on both sides, so selling into reserves that pay out
+        // the quote amount of the recorded buy must charge the fees the program recorded for it
+        let event = buy_event();
+        let reserves = Reserves { base: 1_000, quote: 2 * event.quote_amount_in };
+        let fees = Fees {
+            lp_fee_basis_points: event.lp_fee_basis_points,
+            protocol_fee_basis_points: event.protocol_fee_basis_points,
+        };
+
+        let quote = sell_base_input(1_000, reserves, fees).unwrap();
+
+        assert_eq!(
+            quote,
+            SellQuote {
+                base_amount_in: 1_000,
+                quote_amount_out: event.quote_amount_in,
+                lp_fee: event.lp_fee,
+                protocol_fee: event.protocol_fee,
+                quote_amount_out_without_lp_fee: event.quote_amount_in - event.lp_fee,
+                user_quote_amount_out: event.quote_amount_in - event.lp_fee - event.protocol_fee,
+            }
+        );
+    }
+
+    #[test]
+    fn test_sell_base_input_rounds_quote_amount_out_down() {
+        // 10 * 1 / (3 + 1) = 2.5
+        let quote = sell_base_input(1, Reserves { base: 3, quote: 10 }, Fees::default()).unwrap();
+
+        assert_eq!(quote.quote_amount_out, 2);
+        assert_eq!(quote.user_quote_amount_out, 2);
+    }
+
+    #[test]
+    fn test_buy_more_than_pool_reserves() {
+        let reserves = Reserves { base: 1_000, quote: 1_000 };
+
+        assert_eq!(
+            buy_base_output(1_000, reserves, FEES),
+            Err(Error::BuyMoreBaseAmountThanPoolReserves {
+                base_amount_out: 1_000,
+                pool_base_token_reserves: 1_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_quotes_overflow_like_the_program() {
+        let reserves = Reserves { base: u64::MAX, quote: u64::MAX };
+
+        assert_eq!(buy_base_output(u64::MAX - 1, reserves, FEES), Err(Error::Overflow));
+        let fees = Fees { lp_fee_basis_points: 10_000, protocol_fee_basis_points: 5 };
+        assert_eq!(
+            sell_base_input(1_000, Reserves { base: 1_000, quote: 1_000 }, fees),
+            Err(Error::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_reserves_after_recorded_buy() {
+        let event = buy_event();
+
+        let reserves = Reserves::after_event(&Event::Buy(event)).unwrap();
+
+        assert_eq!(reserves, Reserves { base: 51_037_172_698_390, quote: 657_393_057_454 });
+    }
+}
@@ -0,0 +1,185 @@
+//! Test support: a local HTTP server speaking JSON-RPC, and helpers to build the accounts it
+//! serves
+//!
+//! Clients built on [`MockRpc::url`] go through the whole `reqwest` transport and JSON-RPC
+//! encoding, unlike the mock senders of `solana-rpc-client`.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anchor_client::{
+    anchor_lang::{prelude::Pubkey, AccountSerialize},
+    solana_sdk::commitment_config::CommitmentConfig,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// Answer to a request: its result, or a JSON-RPC error object
+pub type Reply = Result<Value, Value>;
+
+type Handler = dyn Fn(&str, &Value) -> Reply + Send + Sync;
+
+/// HTTP JSON-RPC server answering every request with `handler(method, params)`
+#[derive(Debug)]
+pub struct MockRpc {
+    url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+    server: JoinHandle<()>,
+}
+
+impl MockRpc {
+    /// # Panics
+    /// Panics if no local port can be bound
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&str, &Value) -> Reply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock RPC");
+        let url = format!("http://{}", listener.local_addr().expect("Failed to get local address"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let server = tokio::spawn({
+            let requests = Arc::clone(&requests);
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let _unused =
+                        tokio::spawn(serve(stream, Arc::clone(&handler), Arc::clone(&requests)));
+                }
+            }
+        });
+        Self { url, requests, server }
+    }
+
+    #[must_use]
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Client of the server at confirmed commitment
+    #[must_use]
+    pub fn client(&self) -> RpcClient {
+        RpcClient::new_with_commitment(self.url(), CommitmentConfig::confirmed())
+    }
+
+    /// Requests received so far, across connections
+    #[must_use]
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Methods of the requests received so far, in order
+    #[must_use]
+    pub fn methods(&self) -> Vec<String> {
+        self.requests()
+            .iter()
+            .map(|request| request["method"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+}
+
+impl Drop for MockRpc {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Encoded account as served by `getAccountInfo` and `getMultipleAccounts`
+#[must_use]
+pub fn ui_account(data: &[u8], owner: &Pubkey) -> Value {
+    json!({
+        "data": [STANDARD.encode(data), "base64"],
+        "executable": false,
+        "lamports": 2_039_280,
+        "owner": owner.to_string(),
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+/// Encoded Anchor account owned by `pump_amm`
+///
+/// # Panics
+/// Panics if `account` cannot be serialized
+#[must_use]
+pub fn program_account(account: &impl AccountSerialize) -> Value {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).expect("Failed to serialize account");
+    ui_account(&data, &crate::ID)
+}
+
+/// Encoded token account of `program`, holding `amount` of `mint` for `owner`
+#[must_use]
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64, program: &Pubkey) -> Value {
+    let mut data = vec![0; 165];
+    data[..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    // Initialized
+    data[108] = 1;
+    ui_account(&data, program)
+}
+
+/// Result of a request read at `slot`, such as `getAccountInfo`
+#[must_use]
+pub fn with_context(slot: u64, value: Value) -> Value {
+    let mut result = json!({ "context": { "slot": slot } });
+    result["value"] = value;
+    result
+}
+
+async fn serve(stream: TcpStream, handler: Arc<Handler>, requests: Arc<Mutex<Vec<Value>>>) {
+    let mut stream = BufReader::new(stream);
+    // Connections are kept alive, so requests are read one after the other until the client
+    // leaves
+    while let Some(body) = read_request(&mut stream).await {
+        let Ok(request) = serde_json::from_slice::<Value>(&body) else {
+            return;
+        };
+        requests.lock().unwrap_or_else(PoisonError::into_inner).push(request.clone());
+        let method = request["method"].as_str().unwrap_or_default();
+        let response = match handler(method, &request["params"]) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": request["id"] }),
+            Err(error) => json!({ "jsonrpc": "2.0", "error": error, "id": request["id"] }),
+        }
+        .to_string();
+        let head = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+            response.len()
+        );
+        let stream = stream.get_mut();
+        if stream.write_all(head.as_bytes()).await.is_err()
+            || stream.write_all(response.as_bytes()).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Body of the next request, `None` once the connection is closed
+async fn read_request(stream: &mut BufReader<TcpStream>) -> Option<Vec<u8>> {
+    let mut content_length = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await.ok()? == 0 {
+            return None;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok()?;
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    let _unused = stream.read_exact(&mut body).await.ok()?;
+    Some(body)
+}
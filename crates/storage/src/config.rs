@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Config {
+    /// Persist every decoded event to the database
+    #[serde(default = "Config::default_enabled")]
+    pub enabled: bool,
+
+    /// Database file, created with its schema on first use
+    #[serde(default = "Config::default_path")]
+    pub path: PathBuf,
+
+    /// Time to wait for a lock held by another connection before failing
+    #[serde(default = "Config::default_busy_timeout_ms")]
+    pub busy_timeout_ms: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            path: Self::default_path(),
+            busy_timeout_ms: Self::default_busy_timeout_ms(),
+        }
+    }
+}
+
+impl Config {
+    #[inline]
+    #[must_use]
+    pub const fn default_enabled() -> bool {
+        false
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        PathBuf::from("heracles.db")
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_busy_timeout_ms() -> u64 {
+        5_000
+    }
+}
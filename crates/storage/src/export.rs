@@ -0,0 +1,454 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use arrow_array::{
+    ArrayRef, RecordBatch, StringArray, TimestampSecondArray, UInt32Array, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::NaiveDate;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use tracing::info;
+
+use crate::{
+    Bound, BuildRecordBatchSnafu, CreateExportFileSnafu, Result, Store, TradeRow, WriteCsvSnafu,
+    WriteExportFileSnafu, WriteJsonSnafu, WriteParquetSnafu,
+};
+
+/// Rows buffered per Parquet row group
+const BATCH_SIZE: usize = 8_192;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Parquet,
+    Csv,
+    Ndjson,
+}
+
+impl Format {
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "parquet" => Ok(Self::Parquet),
+            "csv" => Ok(Self::Csv),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            _ => Err(format!("Unknown export format `{s}`, expected parquet, csv or ndjson")),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// Files written by an export
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// One file per day, in date order
+    pub files: Vec<PathBuf>,
+    pub trades: usize,
+}
+
+/// Write the trades from `from` (inclusive) to `to` (exclusive) under `dir`, partitioned by day
+///
+/// Each day goes to `dir/date=YYYY-MM-DD/trades.<format>`, replacing any previous export.
+///
+/// # Errors
+/// Returns an error if the store cannot be read or a file cannot be written
+pub fn export_trades(
+    store: &Store,
+    from: Option<Bound>,
+    to: Option<Bound>,
+    format: Format,
+    dir: &Path,
+) -> Result<Summary> {
+    let mut files = Vec::new();
+    let mut open: Option<(NaiveDate, Partition)> = None;
+    // Trades come in time order, so a day is done once the next one starts and only its file is
+    // open
+    let trades = store.for_each_trade(from, to, |trade| {
+        let date = trade.timestamp.date_naive();
+        match &mut open {
+            Some((day, partition)) if date == *day => partition.write(trade),
+            _ => {
+                if let Some((_, partition)) = open.take() {
+                    files.push(partition.finish()?);
+                }
+                let (_, partition) = open.insert((date, Partition::create(dir, date, format)?));
+                partition.write(trade)
+            }
+        }
+    })?;
+    if let Some((_, partition)) = open {
+        files.push(partition.finish()?);
+    }
+
+    info!("Exported {trades} trades to {} {format} files under {}", files.len(), dir.display());
+    Ok(Summary { files, trades })
+}
+
+/// Flat view of a trade, with keys in base58 and time in RFC 3339
+#[derive(Serialize)]
+struct Record<'a> {
+    signature: &'a str,
+    event_index: usize,
+    slot: u64,
+    timestamp: String,
+    pool: String,
+    user: String,
+    side: &'static str,
+    base_amount: u64,
+    quote_amount: u64,
+    lp_fee: u64,
+    protocol_fee: u64,
+    pool_base_reserves: u64,
+    pool_quote_reserves: u64,
+}
+
+impl<'a> From<&'a TradeRow> for Record<'a> {
+    fn from(trade: &'a TradeRow) -> Self {
+        Self {
+            signature: &trade.signature,
+            event_index: trade.event_index,
+            slot: trade.slot,
+            timestamp: trade.timestamp.to_rfc3339(),
+            pool: trade.pool.to_string(),
+            user: trade.user.to_string(),
+            side: trade.side.as_str(),
+            base_amount: trade.base_amount,
+            quote_amount: trade.quote_amount,
+            lp_fee: trade.lp_fee,
+            protocol_fee: trade.protocol_fee,
+            pool_base_reserves: trade.pool_base_reserves,
+            pool_quote_reserves: trade.pool_quote_reserves,
+        }
+    }
+}
+
+struct Partition {
+    path: PathBuf,
+    writer: Writer,
+}
+
+enum Writer {
+    Csv(Box<csv::Writer<File>>),
+    Ndjson(BufWriter<File>),
+    Parquet { writer: Box<ArrowWriter<File>>, buffer: Vec<TradeRow> },
+}
+
+impl Partition {
+    fn create(dir: &Path, date: NaiveDate, format: Format) -> Result<Self> {
+        let partition = dir.join(format!("date={date}"));
+        fs::create_dir_all(&partition).context(CreateExportFileSnafu { path: &partition })?;
+        let path = partition.join(format!("trades.{}", format.extension()));
+        let file = File::create(&path).context(CreateExportFileSnafu { path: &path })?;
+        let writer = match format {
+            Format::Csv => Writer::Csv(Box::new(csv::Writer::from_writer(file))),
+            Format::Ndjson => Writer::Ndjson(BufWriter::new(file)),
+            Format::Parquet => {
+                let properties =
+                    WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+                let writer = ArrowWriter::try_new(file, schema(), Some(properties))
+                    .context(WriteParquetSnafu { path: &path })?;
+                Writer::Parquet { writer: Box::new(writer), buffer: Vec::with_capacity(BATCH_SIZE) }
+            }
+        };
+        Ok(Self { path, writer })
+    }
+
+    fn write(&mut self, trade: TradeRow) -> Result<()> {
+        let path = &self.path;
+        match &mut self.writer {
+            Writer::Csv(writer) => {
+                writer.serialize(Record::from(&trade)).context(WriteCsvSnafu { path })
+            }
+            Writer::Ndjson(writer) => {
+                serde_json::to_writer(&mut *writer, &Record::from(&trade))
+                    .context(WriteJsonSnafu { path })?;
+                writer.write_all(b"\n").context(WriteExportFileSnafu { path })
+            }
+            Writer::Parquet { writer, buffer } => {
+                buffer.push(trade);
+                if buffer.len() >= BATCH_SIZE {
+                    write_batch(writer, buffer, path)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self) -> Result<PathBuf> {
+        let path = self.path;
+        match self.writer {
+            Writer::Csv(mut writer) => {
+                writer.flush().context(WriteExportFileSnafu { path: &path })?;
+            }
+            Writer::Ndjson(mut writer) => {
+                writer.flush().context(WriteExportFileSnafu { path: &path })?;
+            }
+            Writer::Parquet { mut writer, mut buffer } => {
+                write_batch(&mut writer, &mut buffer, &path)?;
+                let _unused = writer.close().context(WriteParquetSnafu { path: &path })?;
+            }
+        }
+        Ok(path)
+    }
+}
+
+fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("signature", DataType::Utf8, false),
+        Field::new("event_index", DataType::UInt32, false),
+        Field::new("slot", DataType::UInt64, false),
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Second, Some("UTC".into())), false),
+        Field::new("pool", DataType::Utf8, false),
+        Field::new("user", DataType::Utf8, false),
+        Field::new("side", DataType::Utf8, false),
+        Field::new("base_amount", DataType::UInt64, false),
+        Field::new("quote_amount", DataType::UInt64, false),
+        Field::new("lp_fee", DataType::UInt64, false),
+        Field::new("protocol_fee", DataType::UInt64, false),
+        Field::new("pool_base_reserves", DataType::UInt64, false),
+        Field::new("pool_quote_reserves", DataType::UInt64, false),
+    ]))
+}
+
+fn write_batch(
+    writer: &mut ArrowWriter<File>,
+    trades: &mut Vec<TradeRow>,
+    path: &Path,
+) -> Result<()> {
+    if trades.is_empty() {
+        return Ok(());
+    }
+    let u64_column = |f: fn(&TradeRow) -> u64| -> ArrayRef {
+        Arc::new(trades.iter().map(f).collect::<UInt64Array>())
+    };
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            trades.iter().map(|trade| Some(trade.signature.as_str())).collect::<StringArray>(),
+        ),
+        Arc::new(
+            trades
+                .iter()
+                .map(|trade| u32::try_from(trade.event_index).unwrap_or(u32::MAX))
+                .collect::<UInt32Array>(),
+        ),
+        u64_column(|trade| trade.slot),
+        Arc::new(
+            trades
+                .iter()
+                .map(|trade| Some(trade.timestamp.timestamp()))
+                .collect::<TimestampSecondArray>()
+                .with_timezone("UTC"),
+        ),
+        Arc::new(trades.iter().map(|trade| Some(trade.pool.to_string())).collect::<StringArray>()),
+        Arc::new(trades.iter().map(|trade| Some(trade.user.to_string())).collect::<StringArray>()),
+        Arc::new(trades.iter().map(|trade| Some(trade.side.as_str())).collect::<StringArray>()),
+        u64_column(|trade| trade.base_amount),
+        u64_column(|trade| trade.quote_amount),
+        u64_column(|trade| trade.lp_fee),
+        u64_column(|trade| trade.protocol_fee),
+        u64_column(|trade| trade.pool_base_reserves),
+        u64_column(|trade| trade.pool_quote_reserves),
+    ];
+    let batch = RecordBatch::try_new(schema(), columns).context(BuildRecordBatchSnafu)?;
+    writer.write(&batch).context(WriteParquetSnafu { path })?;
+    trades.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use chrono::DateTime;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use pump_amm::{
+        pump_amm::events::{BuyEvent, SellEvent},
+        Event, EventRecord,
+    };
+
+    use super::*;
+
+    /// 2025-03-31T07:47:01Z
+    const TIMESTAMP: i64 = 1_743_407_221;
+    const DAY: i64 = 86_400;
+
+    fn store() -> Store {
+        let (pool, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let records = [
+            (10, Event::Buy(BuyEvent { timestamp: TIMESTAMP, pool, user, ..BuyEvent::default() })),
+            (
+                11,
+                Event::Sell(SellEvent { timestamp: TIMESTAMP, pool, user, ..SellEvent::default() }),
+            ),
+            (
+                20,
+                Event::Buy(BuyEvent {
+                    timestamp: TIMESTAMP + DAY,
+                    base_amount_out: 1_000,
+                    pool,
+                    user,
+                    ..BuyEvent::default()
+                }),
+            ),
+        ]
+        .map(|(slot, event)| EventRecord {
+            signature: format!("sig{slot}"),
+            slot,
+            event_index: 0,
+            invoker: None,
+            cpi_depth: 1,
+            instruction_index: 0,
+            tx_failed: false,
+            event,
+        });
+        let mut store = Store::open_in_memory().unwrap();
+        let _unused = store.insert(&records).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_parse_bound() {
+        assert_eq!("123".parse::<Bound>(), Ok(Bound::Slot(123)));
+        let time = DateTime::from_timestamp(TIMESTAMP, 0).unwrap();
+        assert_eq!("2025-03-31T07:47:01Z".parse::<Bound>(), Ok(Bound::Time(time)));
+        assert_eq!(
+            "2025-03-31".parse::<Bound>(),
+            Ok(Bound::Time(DateTime::from_timestamp(TIMESTAMP - 28_021, 0).unwrap()))
+        );
+        assert!("yesterday".parse::<Bound>().is_err());
+    }
+
+    #[test]
+    fn test_export_partitions_by_day() {
+        let store = store();
+        let dir = tempfile::tempdir().unwrap();
+
+        let summary = export_trades(&store, None, None, Format::Ndjson, dir.path()).unwrap();
+
+        assert_eq!(summary.trades, 3);
+        assert_eq!(
+            summary.files,
+            [
+                dir.path().join("date=2025-03-31/trades.ndjson"),
+                dir.path().join("date=2025-04-01/trades.ndjson"),
+            ]
+        );
+        let first = fs::read_to_string(&summary.files[0]).unwrap();
+        let lines = first
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect::<Vec<serde_json::Value>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["side"], "buy");
+        assert_eq!(lines[1]["side"], "sell");
+        assert_eq!(lines[1]["timestamp"], "2025-03-31T07:47:01+00:00");
+    }
+
+    #[test]
+    fn test_export_writes_late_stamped_trades_to_their_own_day() {
+        let mut store = store();
+        let late = EventRecord {
+            signature: "sig30".to_string(),
+            slot: 30,
+            event_index: 0,
+            invoker: None,
+            cpi_depth: 1,
+            instruction_index: 0,
+            tx_failed: false,
+            event: Event::Sell(SellEvent { timestamp: TIMESTAMP, ..SellEvent::default() }),
+        };
+        let _unused = store.insert(&[late]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let summary = export_trades(&store, None, None, Format::Ndjson, dir.path()).unwrap();
+
+        assert_eq!(summary.trades, 4);
+        assert_eq!(
+            summary.files,
+            [
+                dir.path().join("date=2025-03-31/trades.ndjson"),
+                dir.path().join("date=2025-04-01/trades.ndjson"),
+            ]
+        );
+        let days = summary.files.iter().map(|file| {
+            fs::read_to_string(file)
+                .unwrap()
+                .lines()
+                .map(|line| {
+                    let line: serde_json::Value = serde_json::from_str(line).unwrap();
+                    line["signature"].as_str().unwrap().to_string()
+                })
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(days.collect::<Vec<_>>(), [vec!["sig10", "sig11", "sig30"], vec!["sig20"]]);
+    }
+
+    #[test]
+    fn test_export_range() {
+        let store = store();
+        let dir = tempfile::tempdir().unwrap();
+
+        let summary =
+            export_trades(&store, Some(Bound::Slot(11)), None, Format::Csv, dir.path()).unwrap();
+        assert_eq!(summary.trades, 2);
+
+        let to = "2025-04-01".parse().ok();
+        let summary = export_trades(&store, None, to, Format::Csv, dir.path()).unwrap();
+        assert_eq!(summary.trades, 2);
+        assert_eq!(summary.files.len(), 1);
+        let mut reader = csv::Reader::from_path(&summary.files[0]).unwrap();
+        assert_eq!(
+            reader.headers().unwrap().iter().take(3).collect::<Vec<_>>(),
+            ["signature", "event_index", "slot"]
+        );
+        assert_eq!(reader.records().count(), 2);
+    }
+
+    #[test]
+    fn test_export_parquet() {
+        let store = store();
+        let dir = tempfile::tempdir().unwrap();
+
+        let summary = export_trades(&store, None, None, Format::Parquet, dir.path()).unwrap();
+
+        let file = File::open(&summary.files[1]).unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(batch.num_rows(), 1);
+        let base_amount =
+            batch.column_by_name("base_amount").unwrap().as_any().downcast_ref::<UInt64Array>();
+        assert_eq!(base_amount.unwrap().value(0), 1_000);
+    }
+}
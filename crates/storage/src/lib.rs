@@ -0,0 +1,57 @@
+mod config;
+pub mod export;
+mod migration;
+mod query;
+mod store;
+
+use std::path::PathBuf;
+
+use snafu::Snafu;
+
+pub use self::{
+    config::Config,
+    query::{Bound, MintRow, PoolRow, TradeRow, TradeSide, TraderRow},
+    store::Store,
+};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Failed to open database {}: {}", path.display(), source))]
+    Open { path: PathBuf, source: rusqlite::Error },
+
+    #[snafu(display("Failed to apply database migration {}: {}", version, source))]
+    Migrate { version: usize, source: rusqlite::Error },
+
+    #[snafu(display("Failed to insert events of transaction {}: {}", signature, source))]
+    Insert { signature: String, source: rusqlite::Error },
+
+    #[snafu(display("Failed to query {}: {}", query, source))]
+    Query { query: &'static str, source: rusqlite::Error },
+
+    #[snafu(display("Failed to decode stored event {}#{}: {}", signature, event_index, source))]
+    DecodeEvent { signature: String, event_index: usize, source: pump_amm::Error },
+
+    #[snafu(display("Stored event {}#{} is not a pump_amm event", signature, event_index))]
+    UnknownEvent { signature: String, event_index: usize },
+
+    #[snafu(display("Failed to create export file {}: {}", path.display(), source))]
+    CreateExportFile { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("Failed to write export file {}: {}", path.display(), source))]
+    WriteExportFile { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("Failed to write CSV file {}: {}", path.display(), source))]
+    WriteCsv { path: PathBuf, source: csv::Error },
+
+    #[snafu(display("Failed to write JSON file {}: {}", path.display(), source))]
+    WriteJson { path: PathBuf, source: serde_json::Error },
+
+    #[snafu(display("Failed to build record batch: {}", source))]
+    BuildRecordBatch { source: arrow_schema::ArrowError },
+
+    #[snafu(display("Failed to write Parquet file {}: {}", path.display(), source))]
+    WriteParquet { path: PathBuf, source: parquet::errors::ParquetError },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,109 @@
+use rusqlite::Connection;
+use snafu::ResultExt;
+use tracing::info;
+
+use crate::{MigrateSnafu, Result};
+
+/// Schema changes, applied in order; the database records how many ran in `user_version`
+///
+/// Never edit a released migration, append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: events, keyed by their position in the transaction, and the tables derived from them
+    "
+    CREATE TABLE events (
+        signature         TEXT    NOT NULL,
+        event_index       INTEGER NOT NULL,
+        slot              INTEGER NOT NULL,
+        timestamp         INTEGER NOT NULL,
+        kind              TEXT    NOT NULL,
+        invoker           TEXT,
+        cpi_depth         INTEGER NOT NULL,
+        instruction_index INTEGER NOT NULL,
+        tx_failed         INTEGER NOT NULL,
+        data              BLOB    NOT NULL,
+        PRIMARY KEY (signature, event_index)
+    ) WITHOUT ROWID;
+    CREATE INDEX events_slot ON events (slot);
+
+    CREATE TABLE trades (
+        signature           TEXT    NOT NULL,
+        event_index         INTEGER NOT NULL,
+        slot                INTEGER NOT NULL,
+        timestamp           INTEGER NOT NULL,
+        pool                TEXT    NOT NULL,
+        user                TEXT    NOT NULL,
+        side                TEXT    NOT NULL,
+        base_amount         INTEGER NOT NULL,
+        quote_amount        INTEGER NOT NULL,
+        lp_fee              INTEGER NOT NULL,
+        protocol_fee        INTEGER NOT NULL,
+        pool_base_reserves  INTEGER NOT NULL,
+        pool_quote_reserves INTEGER NOT NULL,
+        PRIMARY KEY (signature, event_index)
+    ) WITHOUT ROWID;
+    CREATE INDEX trades_pool_timestamp ON trades (pool, timestamp);
+    CREATE INDEX trades_user ON trades (user);
+
+    CREATE TABLE pools (
+        address      TEXT    PRIMARY KEY,
+        creator      TEXT    NOT NULL,
+        base_mint    TEXT    NOT NULL,
+        quote_mint   TEXT    NOT NULL,
+        lp_mint      TEXT    NOT NULL,
+        slot         INTEGER NOT NULL,
+        timestamp    INTEGER NOT NULL,
+        signature    TEXT    NOT NULL
+    ) WITHOUT ROWID;
+    CREATE INDEX pools_creator ON pools (creator);
+    CREATE INDEX pools_base_mint ON pools (base_mint);
+
+    CREATE TABLE mints (
+        address    TEXT    PRIMARY KEY,
+        decimals   INTEGER NOT NULL,
+        first_slot INTEGER NOT NULL
+    ) WITHOUT ROWID;
+
+    CREATE TABLE traders (
+        address    TEXT    PRIMARY KEY,
+        first_slot INTEGER NOT NULL,
+        last_slot  INTEGER NOT NULL,
+        buys       INTEGER NOT NULL,
+        sells      INTEGER NOT NULL
+    ) WITHOUT ROWID;
+    ",
+];
+
+/// Bring the schema up to date, returning the number of migrations applied
+pub fn migrate(connection: &mut Connection) -> Result<usize> {
+    let current: usize = connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .context(MigrateSnafu { version: 0_usize })?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let transaction = connection.transaction().context(MigrateSnafu { version })?;
+        transaction.execute_batch(migration).context(MigrateSnafu { version })?;
+        transaction
+            .pragma_update(None, "user_version", version)
+            .context(MigrateSnafu { version })?;
+        transaction.commit().context(MigrateSnafu { version })?;
+        info!("Applied database migration {version}");
+    }
+    Ok(MIGRATIONS.len().saturating_sub(current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_is_idempotent() {
+        let mut connection = Connection::open_in_memory().unwrap();
+
+        assert_eq!(migrate(&mut connection).unwrap(), MIGRATIONS.len());
+        assert_eq!(migrate(&mut connection).unwrap(), 0);
+
+        let version: usize =
+            connection.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+}
@@ -0,0 +1,422 @@
+use std::{fmt, str::FromStr};
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use chrono::{DateTime, NaiveDate, Utc};
+use pump_amm::{Event, EventRecord};
+use rusqlite::{
+    params,
+    types::{FromSql, FromSqlError, FromSqlResult, Type, ValueRef},
+    OptionalExtension, Row,
+};
+use snafu::ResultExt;
+
+use crate::{QuerySnafu, Result, Store};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Buy => "buy",
+            Self::Sell => "sell",
+        }
+    }
+}
+
+impl FromSql for TradeSide {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "buy" => Ok(Self::Buy),
+            "sell" => Ok(Self::Sell),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+/// Bound of a range of trades, either a slot or a block time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Slot(u64),
+    Time(DateTime<Utc>),
+}
+
+impl Bound {
+    fn slot(bound: Option<Self>) -> Option<i64> {
+        match bound {
+            Some(Self::Slot(slot)) => Some(clamped(slot)),
+            _ => None,
+        }
+    }
+
+    const fn timestamp(bound: Option<Self>) -> Option<i64> {
+        match bound {
+            Some(Self::Time(time)) => Some(time.timestamp()),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Bound {
+    type Err = String;
+
+    /// Parse a slot number, an RFC 3339 time or a `YYYY-MM-DD` date, taken as midnight UTC
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(slot) = s.parse() {
+            return Ok(Self::Slot(slot));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Self::Time(time.to_utc()));
+        }
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|time| Self::Time(time.and_utc()))
+            .ok_or_else(|| {
+                format!("Invalid bound `{s}`, expected a slot, an RFC 3339 time or a date")
+            })
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Slot(slot) => write!(f, "slot {slot}"),
+            Self::Time(time) => write!(f, "{}", time.to_rfc3339()),
+        }
+    }
+}
+
+/// A buy or sell that took effect on-chain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TradeRow {
+    pub signature: String,
+    pub event_index: usize,
+    pub slot: u64,
+    pub timestamp: DateTime<Utc>,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub side: TradeSide,
+    /// Base tokens bought or sold
+    pub base_amount: u64,
+    /// Quote tokens paid or received by the user, fees included
+    pub quote_amount: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    /// Pool reserves before the trade
+    pub pool_base_reserves: u64,
+    pub pool_quote_reserves: u64,
+}
+
+/// A pool created on-chain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolRow {
+    pub address: Pubkey,
+    pub creator: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub slot: u64,
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+}
+
+/// A mint traded on one of the stored pools
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MintRow {
+    pub address: Pubkey,
+    pub decimals: u8,
+    /// Slot of the first pool created with the mint
+    pub first_slot: u64,
+}
+
+/// A user who bought or sold on one of the stored pools
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraderRow {
+    pub address: Pubkey,
+    pub first_slot: u64,
+    pub last_slot: u64,
+    pub buys: u64,
+    pub sells: u64,
+}
+
+impl TradeRow {
+    /// Trade carried by a buy or sell event, `None` for any other event
+    #[must_use]
+    pub fn from_record(record: &EventRecord) -> Option<Self> {
+        let (timestamp, pool, user, side, amounts) = match &record.event {
+            Event::Buy(event) => (
+                event.timestamp,
+                event.pool,
+                event.user,
+                TradeSide::Buy,
+                [
+                    event.base_amount_out,
+                    event.user_quote_amount_in,
+                    event.lp_fee,
+                    event.protocol_fee,
+                    event.pool_base_token_reserves,
+                    event.pool_quote_token_reserves,
+                ],
+            ),
+            Event::Sell(event) => (
+                event.timestamp,
+                event.pool,
+                event.user,
+                TradeSide::Sell,
+                [
+                    event.base_amount_in,
+                    event.user_quote_amount_out,
+                    event.lp_fee,
+                    event.protocol_fee,
+                    event.pool_base_token_reserves,
+                    event.pool_quote_token_reserves,
+                ],
+            ),
+            _ => return None,
+        };
+        let [base_amount, quote_amount, lp_fee, protocol_fee, pool_base_reserves, pool_quote_reserves] =
+            amounts;
+        Some(Self {
+            signature: record.signature.clone(),
+            event_index: record.event_index,
+            slot: record.slot,
+            timestamp: DateTime::from_timestamp(timestamp, 0)?,
+            pool,
+            user,
+            side,
+            base_amount,
+            quote_amount,
+            lp_fee,
+            protocol_fee,
+            pool_base_reserves,
+            pool_quote_reserves,
+        })
+    }
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            signature: row.get(0)?,
+            event_index: row.get(1)?,
+            slot: unsigned(row, 2)?,
+            timestamp: timestamp(row, 3)?,
+            pool: pubkey(row, 4)?,
+            user: pubkey(row, 5)?,
+            side: row.get(6)?,
+            base_amount: unsigned(row, 7)?,
+            quote_amount: unsigned(row, 8)?,
+            lp_fee: unsigned(row, 9)?,
+            protocol_fee: unsigned(row, 10)?,
+            pool_base_reserves: unsigned(row, 11)?,
+            pool_quote_reserves: unsigned(row, 12)?,
+        })
+    }
+}
+
+impl PoolRow {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            address: pubkey(row, 0)?,
+            creator: pubkey(row, 1)?,
+            base_mint: pubkey(row, 2)?,
+            quote_mint: pubkey(row, 3)?,
+            lp_mint: pubkey(row, 4)?,
+            slot: unsigned(row, 5)?,
+            timestamp: timestamp(row, 6)?,
+            signature: row.get(7)?,
+        })
+    }
+}
+
+impl Store {
+    /// Trades of `pool` whose block time is within `[from, to)`, oldest first
+    ///
+    /// # Errors
+    /// Returns an error if the query fails
+    pub fn trades_by_pool(
+        &self,
+        pool: &Pubkey,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<TradeRow>> {
+        const QUERY: &str = "trades by pool";
+        self.connection
+            .prepare_cached(
+                "SELECT signature, event_index, slot, timestamp, pool, user, side, base_amount,
+                        quote_amount, lp_fee, protocol_fee, pool_base_reserves, pool_quote_reserves
+                 FROM trades
+                 WHERE pool = ?1 AND timestamp >= ?2 AND timestamp < ?3
+                 ORDER BY slot, signature, event_index",
+            )
+            .and_then(|mut statement| {
+                statement
+                    .query_map(
+                        params![pool.to_string(), from.timestamp(), to.timestamp()],
+                        TradeRow::from_row,
+                    )?
+                    .collect()
+            })
+            .context(QuerySnafu { query: QUERY })
+    }
+
+    /// Call `f` with every trade from `from` (inclusive) to `to` (exclusive), oldest first,
+    /// returning the number of trades visited
+    ///
+    /// Trades are ordered by time, then slot, so a block stamped earlier than the one before it
+    /// still comes with its day. They are streamed rather than collected, so that whole
+    /// histories can be exported.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails, or the first error returned by `f`
+    pub fn for_each_trade<F>(
+        &self,
+        from: Option<Bound>,
+        to: Option<Bound>,
+        mut f: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(TradeRow) -> Result<()>,
+    {
+        const QUERY: &str = "trades in range";
+        let mut statement = self
+            .connection
+            .prepare_cached(
+                "SELECT signature, event_index, slot, timestamp, pool, user, side, base_amount,
+                        quote_amount, lp_fee, protocol_fee, pool_base_reserves, pool_quote_reserves
+                 FROM trades
+                 WHERE (?1 IS NULL OR slot >= ?1) AND (?2 IS NULL OR timestamp >= ?2)
+                   AND (?3 IS NULL OR slot < ?3) AND (?4 IS NULL OR timestamp < ?4)
+                 ORDER BY timestamp, slot, signature, event_index",
+            )
+            .context(QuerySnafu { query: QUERY })?;
+        let rows = statement
+            .query_map(
+                params![
+                    Bound::slot(from),
+                    Bound::timestamp(from),
+                    Bound::slot(to),
+                    Bound::timestamp(to)
+                ],
+                TradeRow::from_row,
+            )
+            .context(QuerySnafu { query: QUERY })?;
+        let mut count = 0;
+        for trade in rows {
+            f(trade.context(QuerySnafu { query: QUERY })?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Pools created by `creator`, oldest first
+    ///
+    /// # Errors
+    /// Returns an error if the query fails
+    pub fn pools_by_creator(&self, creator: &Pubkey) -> Result<Vec<PoolRow>> {
+        const QUERY: &str = "pools by creator";
+        self.connection
+            .prepare_cached(
+                "SELECT address, creator, base_mint, quote_mint, lp_mint, slot, timestamp, signature
+                 FROM pools
+                 WHERE creator = ?1
+                 ORDER BY slot, address",
+            )
+            .and_then(|mut statement| {
+                statement.query_map(params![creator.to_string()], PoolRow::from_row)?.collect()
+            })
+            .context(QuerySnafu { query: QUERY })
+    }
+
+    /// # Errors
+    /// Returns an error if the query fails
+    pub fn pool(&self, address: &Pubkey) -> Result<Option<PoolRow>> {
+        self.connection
+            .query_row(
+                "SELECT address, creator, base_mint, quote_mint, lp_mint, slot, timestamp, signature
+                 FROM pools
+                 WHERE address = ?1",
+                params![address.to_string()],
+                PoolRow::from_row,
+            )
+            .optional()
+            .context(QuerySnafu { query: "pool" })
+    }
+
+    /// # Errors
+    /// Returns an error if the query fails
+    pub fn mint(&self, address: &Pubkey) -> Result<Option<MintRow>> {
+        self.connection
+            .query_row(
+                "SELECT address, decimals, first_slot FROM mints WHERE address = ?1",
+                params![address.to_string()],
+                |row| {
+                    Ok(MintRow {
+                        address: pubkey(row, 0)?,
+                        decimals: row.get(1)?,
+                        first_slot: unsigned(row, 2)?,
+                    })
+                },
+            )
+            .optional()
+            .context(QuerySnafu { query: "mint" })
+    }
+
+    /// # Errors
+    /// Returns an error if the query fails
+    pub fn trader(&self, address: &Pubkey) -> Result<Option<TraderRow>> {
+        self.connection
+            .query_row(
+                "SELECT address, first_slot, last_slot, buys, sells FROM traders WHERE address = ?1",
+                params![address.to_string()],
+                |row| {
+                    Ok(TraderRow {
+                        address: pubkey(row, 0)?,
+                        first_slot: unsigned(row, 1)?,
+                        last_slot: unsigned(row, 2)?,
+                        buys: unsigned(row, 3)?,
+                        sells: unsigned(row, 4)?,
+                    })
+                },
+            )
+            .optional()
+            .context(QuerySnafu { query: "trader" })
+    }
+}
+
+fn pubkey(row: &Row<'_>, index: usize) -> rusqlite::Result<Pubkey> {
+    row.get::<_, String>(index)?
+        .parse()
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(err)))
+}
+
+/// Reinterpret a `u64` as the signed integer `SQLite` stores, preserving every bit
+///
+/// Values above `i64::MAX` come out negative, so this is only fit for columns that are never
+/// compared, such as amounts.
+pub const fn signed(value: u64) -> i64 {
+    i64::from_ne_bytes(value.to_ne_bytes())
+}
+
+/// Convert a `u64` that is compared in queries, such as a slot, saturating at `i64::MAX`
+pub fn clamped(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+/// Read a `u64` column written with [`signed`] or [`clamped`]
+pub fn unsigned(row: &Row<'_>, index: usize) -> rusqlite::Result<u64> {
+    row.get::<_, i64>(index).map(|value| u64::from_ne_bytes(value.to_ne_bytes()))
+}
+
+fn timestamp(row: &Row<'_>, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::from_timestamp(row.get(index)?, 0).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            Type::Integer,
+            "timestamp out of range".into(),
+        )
+    })
+}
@@ -0,0 +1,489 @@
+use std::{ops::RangeInclusive, path::Path, time::Duration};
+
+use pump_amm::{Event, EventRecord};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use snafu::{OptionExt, ResultExt};
+
+use crate::{
+    migration,
+    query::{clamped, signed, unsigned, MintRow, TradeRow, TradeSide},
+    Config, DecodeEventSnafu, InsertSnafu, OpenSnafu, QuerySnafu, Result, UnknownEventSnafu,
+};
+
+/// Database of decoded events and of the pools, mints and traders derived from them
+#[derive(Debug)]
+pub struct Store {
+    pub(crate) connection: Connection,
+}
+
+impl Store {
+    /// Open the database at `config.path`, creating it if needed, and migrate its schema
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened or migrated
+    pub fn open(config: &Config) -> Result<Self> {
+        let path = &config.path;
+        let connection = Connection::open(path).context(OpenSnafu { path })?;
+        connection
+            .busy_timeout(Duration::from_millis(config.busy_timeout_ms))
+            .context(OpenSnafu { path })?;
+        // Let readers such as `heracles export` run while the listener writes
+        connection
+            .pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            .context(OpenSnafu { path })?;
+        Self::with_connection(connection)
+    }
+
+    /// Open a private in-memory database, discarded once dropped
+    ///
+    /// # Errors
+    /// Returns an error if the schema cannot be created
+    pub fn open_in_memory() -> Result<Self> {
+        let connection =
+            Connection::open_in_memory().context(OpenSnafu { path: Path::new(":memory:") })?;
+        Self::with_connection(connection)
+    }
+
+    fn with_connection(mut connection: Connection) -> Result<Self> {
+        let _unused = migration::migrate(&mut connection)?;
+        Ok(Self { connection })
+    }
+
+    /// Persist `records` and update the tables derived from them, in a single transaction
+    ///
+    /// Records already stored, identified by their signature and event index, are skipped, so
+    /// that the same transaction delivered by both backfill and the live stream is only counted
+    /// once. Events of failed transactions are stored but not derived, as they took no effect.
+    /// Returns the number of records actually inserted.
+    ///
+    /// # Errors
+    /// Returns an error if a record cannot be written, in which case none of them are
+    pub fn insert(&mut self, records: &[EventRecord]) -> Result<usize> {
+        let Some(first) = records.first() else {
+            return Ok(0);
+        };
+        let transaction = self
+            .connection
+            .transaction()
+            .context(InsertSnafu { signature: first.signature.clone() })?;
+        let mut inserted = 0;
+        for record in records {
+            let signature = &record.signature;
+            if insert_record(&transaction, record).context(InsertSnafu { signature })? {
+                inserted += 1;
+            }
+        }
+        transaction.commit().context(InsertSnafu { signature: first.signature.clone() })?;
+        Ok(inserted)
+    }
+
+    /// Stored events of the transactions that landed within `slots`, in slot order
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored event cannot be decoded
+    pub fn events(&self, slots: RangeInclusive<u64>) -> Result<Vec<EventRecord>> {
+        const QUERY: &str = "events in slot range";
+        let mut statement = self
+            .connection
+            .prepare_cached(
+                "SELECT signature, event_index, slot, invoker, cpi_depth, instruction_index,
+                        tx_failed, data
+                 FROM events
+                 WHERE slot BETWEEN ?1 AND ?2
+                 ORDER BY slot, signature, event_index",
+            )
+            .context(QuerySnafu { query: QUERY })?;
+        let rows = statement
+            .query_map(params![clamped(*slots.start()), clamped(*slots.end())], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, usize>(1)?,
+                    unsigned(row, 2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get::<_, Vec<u8>>(7)?,
+                ))
+            })
+            .context(QuerySnafu { query: QUERY })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (
+                signature,
+                event_index,
+                slot,
+                invoker,
+                cpi_depth,
+                instruction_index,
+                tx_failed,
+                data,
+            ) = row.context(QuerySnafu { query: QUERY })?;
+            let event = Event::from_bytes(&data)
+                .context(DecodeEventSnafu { signature: signature.clone(), event_index })?
+                .context(UnknownEventSnafu { signature: signature.clone(), event_index })?;
+            records.push(EventRecord {
+                signature,
+                slot,
+                event_index,
+                invoker: invoker.and_then(|invoker| invoker.parse().ok()),
+                cpi_depth,
+                instruction_index,
+                tx_failed,
+                event,
+            });
+        }
+        Ok(records)
+    }
+
+    /// Whether the event at `event_index` of `signature` is stored
+    ///
+    /// # Errors
+    /// Returns an error if the query fails
+    pub fn contains(&self, signature: &str, event_index: usize) -> Result<bool> {
+        self.connection
+            .query_row(
+                "SELECT 1 FROM events WHERE signature = ?1 AND event_index = ?2",
+                params![signature, event_index],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|row| row.is_some())
+            .context(QuerySnafu { query: "event by signature" })
+    }
+}
+
+/// Insert a record, returning whether it was not stored yet
+///
+/// `SQLite` integers are signed, so amounts are stored bit for bit with [`signed`] and read back
+/// with [`unsigned`]; amounts above `i64::MAX` would otherwise fail to bind. Slots are compared
+/// in queries, so they saturate with [`clamped`] instead, keeping their order.
+fn insert_record(transaction: &Transaction<'_>, record: &EventRecord) -> rusqlite::Result<bool> {
+    let inserted = transaction
+        .prepare_cached(
+            "INSERT OR IGNORE INTO events (signature, event_index, slot, timestamp, kind, invoker,
+                                           cpi_depth, instruction_index, tx_failed, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?
+        .execute(params![
+            record.signature,
+            record.event_index,
+            clamped(record.slot),
+            record.event.timestamp(),
+            record.event.name(),
+            record.invoker.map(|invoker| invoker.to_string()),
+            record.cpi_depth,
+            record.instruction_index,
+            record.tx_failed,
+            record.event.to_bytes(),
+        ])?
+        > 0;
+    if inserted && !record.tx_failed {
+        derive(transaction, record)?;
+    }
+    Ok(inserted)
+}
+
+fn derive(transaction: &Transaction<'_>, record: &EventRecord) -> rusqlite::Result<()> {
+    if let Some(trade) = TradeRow::from_record(record) {
+        insert_trade(transaction, &trade)?;
+    }
+    if let Event::CreatePool(event) = &record.event {
+        let _unused = transaction
+            .prepare_cached(
+                "INSERT OR IGNORE INTO pools (address, creator, base_mint, quote_mint, lp_mint, slot,
+                                              timestamp, signature)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?
+            .execute(params![
+                event.pool.to_string(),
+                event.creator.to_string(),
+                event.base_mint.to_string(),
+                event.quote_mint.to_string(),
+                event.lp_mint.to_string(),
+                clamped(record.slot),
+                event.timestamp,
+                record.signature,
+            ])?;
+        for mint in [
+            MintRow {
+                address: event.base_mint,
+                decimals: event.base_mint_decimals,
+                first_slot: record.slot,
+            },
+            MintRow {
+                address: event.quote_mint,
+                decimals: event.quote_mint_decimals,
+                first_slot: record.slot,
+            },
+        ] {
+            let _unused = transaction
+                .prepare_cached(
+                    "INSERT INTO mints (address, decimals, first_slot) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (address) DO UPDATE SET
+                         first_slot = min(first_slot, excluded.first_slot)",
+                )?
+                .execute(params![
+                    mint.address.to_string(),
+                    mint.decimals,
+                    clamped(mint.first_slot)
+                ])?;
+        }
+    }
+    Ok(())
+}
+
+fn insert_trade(transaction: &Transaction<'_>, trade: &TradeRow) -> rusqlite::Result<()> {
+    let _unused = transaction
+        .prepare_cached(
+            "INSERT OR IGNORE INTO trades (signature, event_index, slot, timestamp, pool, user, side,
+                                           base_amount, quote_amount, lp_fee, protocol_fee,
+                                           pool_base_reserves, pool_quote_reserves)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        )?
+        .execute(params![
+            trade.signature,
+            trade.event_index,
+            clamped(trade.slot),
+            trade.timestamp.timestamp(),
+            trade.pool.to_string(),
+            trade.user.to_string(),
+            trade.side.as_str(),
+            signed(trade.base_amount),
+            signed(trade.quote_amount),
+            signed(trade.lp_fee),
+            signed(trade.protocol_fee),
+            signed(trade.pool_base_reserves),
+            signed(trade.pool_quote_reserves),
+        ])?;
+    let (buys, sells) = match trade.side {
+        TradeSide::Buy => (1, 0),
+        TradeSide::Sell => (0, 1),
+    };
+    let _unused = transaction
+        .prepare_cached(
+            "INSERT INTO traders (address, first_slot, last_slot, buys, sells)
+             VALUES (?1, ?2, ?2, ?3, ?4)
+             ON CONFLICT (address) DO UPDATE SET
+                 first_slot = min(first_slot, excluded.first_slot),
+                 last_slot = max(last_slot, excluded.last_slot),
+                 buys = buys + excluded.buys,
+                 sells = sells + excluded.sells",
+        )?
+        .execute(params![trade.user.to_string(), clamped(trade.slot), buys, sells])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_client::solana_sdk::pubkey::Pubkey;
+    use chrono::DateTime;
+    use pump_amm::pump_amm::events::{BuyEvent, CreatePoolEvent, SellEvent};
+
+    use super::*;
+    use crate::Bound;
+
+    const TIMESTAMP: i64 = 1_743_407_221;
+
+    fn record(signature: &str, slot: u64, event: Event) -> EventRecord {
+        EventRecord {
+            signature: signature.to_string(),
+            slot,
+            event_index: 0,
+            invoker: None,
+            cpi_depth: 1,
+            instruction_index: 0,
+            tx_failed: false,
+            event,
+        }
+    }
+
+    fn buy(pool: Pubkey, user: Pubkey, timestamp: i64) -> Event {
+        Event::Buy(BuyEvent {
+            timestamp,
+            base_amount_out: 1_000,
+            user_quote_amount_in: 2_000,
+            lp_fee: 4,
+            protocol_fee: 1,
+            pool_base_token_reserves: 50_000,
+            pool_quote_token_reserves: 100_000,
+            pool,
+            user,
+            ..BuyEvent::default()
+        })
+    }
+
+    #[test]
+    fn test_insert_is_idempotent() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (pool, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let records = [
+            record("buy", 10, buy(pool, user, TIMESTAMP)),
+            record(
+                "sell",
+                11,
+                Event::Sell(SellEvent { timestamp: TIMESTAMP, pool, user, ..SellEvent::default() }),
+            ),
+        ];
+
+        assert_eq!(store.insert(&records).unwrap(), 2);
+        // Delivered again by backfill after a reconnect
+        assert_eq!(store.insert(&records[..1]).unwrap(), 0);
+
+        let trader = store.trader(&user).unwrap().unwrap();
+        assert_eq!((trader.buys, trader.sells), (1, 1));
+        assert_eq!((trader.first_slot, trader.last_slot), (10, 11));
+        assert!(store.contains("buy", 0).unwrap());
+        assert!(!store.contains("buy", 1).unwrap());
+    }
+
+    #[test]
+    fn test_insert_does_not_derive_failed_transactions() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (pool, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let failed =
+            EventRecord { tx_failed: true, ..record("failed", 10, buy(pool, user, TIMESTAMP)) };
+
+        assert_eq!(store.insert(&[failed]).unwrap(), 1);
+
+        assert!(store.trader(&user).unwrap().is_none());
+        let events = store.events(10..=10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].tx_failed);
+    }
+
+    #[test]
+    fn test_insert_round_trips_amounts_above_i64_max() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (pool, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut event = buy(pool, user, TIMESTAMP);
+        if let Event::Buy(buy) = &mut event {
+            buy.base_amount_out = u64::MAX;
+            buy.pool_base_token_reserves = u64::MAX;
+            buy.pool_quote_token_reserves = u64::MAX;
+        }
+        let slot = 330_012_345;
+
+        assert_eq!(store.insert(&[record("max", slot, event)]).unwrap(), 1);
+
+        let from = DateTime::from_timestamp(TIMESTAMP, 0).unwrap();
+        let to = DateTime::from_timestamp(TIMESTAMP + 1, 0).unwrap();
+        let trades = store.trades_by_pool(&pool, from, to).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].slot, slot);
+        assert_eq!(trades[0].base_amount, u64::MAX);
+        assert_eq!(
+            (trades[0].pool_base_reserves, trades[0].pool_quote_reserves),
+            (u64::MAX, u64::MAX)
+        );
+        assert_eq!(store.trader(&user).unwrap().unwrap().last_slot, slot);
+        assert_eq!(store.events(slot..=slot).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_slot_ranges_up_to_u64_max() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (pool, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let _unused = store
+            .insert(&[
+                record("first", 10, buy(pool, user, TIMESTAMP)),
+                record("second", 20, buy(pool, user, TIMESTAMP)),
+            ])
+            .unwrap();
+
+        assert_eq!(store.events(0..=u64::MAX).unwrap().len(), 2);
+        assert_eq!(store.events(15..=u64::MAX).unwrap().len(), 1);
+        let visited = store
+            .for_each_trade(Some(Bound::Slot(0)), Some(Bound::Slot(u64::MAX)), |_| Ok(()))
+            .unwrap();
+        assert_eq!(visited, 2);
+        let trader = store.trader(&user).unwrap().unwrap();
+        assert_eq!((trader.first_slot, trader.last_slot), (10, 20));
+    }
+
+    #[test]
+    fn test_trades_by_pool_within_time_range() {
+        let mut store = Store::open_in_memory().unwrap();
+        let (pool, other_pool, user) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let _unused = store
+            .insert(&[
+                record("before", 10, buy(pool, user, TIMESTAMP - 1)),
+                record("first", 11, buy(pool, user, TIMESTAMP)),
+                record("other", 12, buy(other_pool, user, TIMESTAMP + 1)),
+                record("second", 13, buy(pool, user, TIMESTAMP + 59)),
+                record("after", 14, buy(pool, user, TIMESTAMP + 60)),
+            ])
+            .unwrap();
+
+        let from = DateTime::from_timestamp(TIMESTAMP, 0).unwrap();
+        let to = DateTime::from_timestamp(TIMESTAMP + 60, 0).unwrap();
+        let trades = store.trades_by_pool(&pool, from, to).unwrap();
+
+        let signatures: Vec<_> = trades.iter().map(|trade| trade.signature.as_str()).collect();
+        assert_eq!(signatures, ["first", "second"]);
+        assert_eq!(
+            trades[0],
+            TradeRow {
+                signature: "first".to_string(),
+                event_index: 0,
+                slot: 11,
+                timestamp: from,
+                pool,
+                user,
+                side: TradeSide::Buy,
+                base_amount: 1_000,
+                quote_amount: 2_000,
+                lp_fee: 4,
+                protocol_fee: 1,
+                pool_base_reserves: 50_000,
+                pool_quote_reserves: 100_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_create_pool_derives_pool_and_mints() {
+        let mut store = Store::open_in_memory().unwrap();
+        let creator = Pubkey::new_unique();
+        let event = CreatePoolEvent {
+            timestamp: TIMESTAMP,
+            creator,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            base_mint_decimals: 6,
+            quote_mint_decimals: 9,
+            pool: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            ..CreatePoolEvent::default()
+        };
+        let _unused = store.insert(&[record("create", 10, Event::CreatePool(event))]).unwrap();
+
+        let pools = store.pools_by_creator(&creator).unwrap();
+        assert_eq!(pools.len(), 1);
+        assert_eq!(pools[0].address, event.pool);
+        assert_eq!(pools[0].base_mint, event.base_mint);
+        assert_eq!(store.pool(&event.pool).unwrap(), Some(pools[0].clone()));
+        assert_eq!(store.mint(&event.base_mint).unwrap().unwrap().decimals, 6);
+        assert_eq!(store.mint(&event.quote_mint).unwrap().unwrap().decimals, 9);
+        assert!(store.pools_by_creator(&Pubkey::new_unique()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_keeps_events_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let config =
+            Config { enabled: true, path: dir.path().join("heracles.db"), ..Config::default() };
+        let records =
+            [record("buy", 10, buy(Pubkey::new_unique(), Pubkey::new_unique(), TIMESTAMP))];
+
+        assert_eq!(Store::open(&config).unwrap().insert(&records).unwrap(), 1);
+        let mut store = Store::open(&config).unwrap();
+
+        assert_eq!(store.insert(&records).unwrap(), 0);
+        let events = store.events(10..=10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].event, Event::Buy(_)));
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use crate::keypair::KeypairConfig;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Config {
+    /// HTTP JSON-RPC endpoint transactions are submitted to
+    #[serde(default = "Config::default_rpc_url")]
+    pub rpc_url: String,
+
+    #[serde(default)]
+    pub keypair: KeypairConfig,
+
+    /// Compute units requested by every transaction
+    #[serde(default = "Config::default_compute_unit_limit")]
+    pub compute_unit_limit: u32,
+
+    /// Priority fee paid per compute unit
+    #[serde(default = "Config::default_priority_fee_micro_lamports")]
+    pub priority_fee_micro_lamports: u64,
+
+    /// Time to wait for a submitted transaction to be confirmed
+    #[serde(default = "Config::default_confirm_timeout_ms")]
+    pub confirm_timeout_ms: u64,
+
+    /// Resubmissions with a fresh blockhash once the previous one has expired
+    #[serde(default = "Config::default_max_retries")]
+    pub max_retries: u32,
+
+    /// Run every trade through `simulateTransaction` instead of sending it
+    #[serde(default = "Config::default_simulate")]
+    pub simulate: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rpc_url: Self::default_rpc_url(),
+            keypair: KeypairConfig::default(),
+            compute_unit_limit: Self::default_compute_unit_limit(),
+            priority_fee_micro_lamports: Self::default_priority_fee_micro_lamports(),
+            confirm_timeout_ms: Self::default_confirm_timeout_ms(),
+            max_retries: Self::default_max_retries(),
+            simulate: Self::default_simulate(),
+        }
+    }
+}
+
+impl Config {
+    #[inline]
+    #[must_use]
+    pub fn default_rpc_url() -> String {
+        "https://api.mainnet-beta.solana.com".to_string()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_compute_unit_limit() -> u32 {
+        200_000
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_priority_fee_micro_lamports() -> u64 {
+        0
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_confirm_timeout_ms() -> u64 {
+        60_000
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_max_retries() -> u32 {
+        3
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_simulate() -> bool {
+        false
+    }
+}
@@ -0,0 +1,125 @@
+use std::{env, fs, path::PathBuf};
+
+use anchor_client::solana_sdk::{bs58, signature::Keypair};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt};
+
+use crate::{DecodeKeypairSnafu, MissingKeypairSnafu, ReadKeypairFileSnafu, Result};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KeypairConfig {
+    /// Environment variable holding a base58 encoded keypair, preferred over `path` when set
+    #[serde(default = "KeypairConfig::default_env")]
+    pub env: String,
+
+    /// Keypair file written by `solana-keygen`, a JSON array of 64 bytes
+    #[serde(default = "KeypairConfig::default_path")]
+    pub path: Option<PathBuf>,
+}
+
+impl Default for KeypairConfig {
+    fn default() -> Self {
+        Self { env: Self::default_env(), path: Self::default_path() }
+    }
+}
+
+impl KeypairConfig {
+    #[inline]
+    #[must_use]
+    pub fn default_env() -> String {
+        "HERACLES_KEYPAIR".to_string()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn default_path() -> Option<PathBuf> {
+        None
+    }
+
+    /// Load the keypair from the environment variable, or else from the keypair file
+    ///
+    /// Errors never include the secret key, nor the content it was decoded from.
+    ///
+    /// # Errors
+    /// Returns an error if neither source is set, or if the keypair cannot be read or decoded
+    pub fn load(&self) -> Result<Keypair> {
+        self.load_with_env(env::var(&self.env).ok())
+    }
+
+    /// Load the keypair from `encoded`, the value of the environment variable, or else from the
+    /// keypair file
+    fn load_with_env(&self, encoded: Option<String>) -> Result<Keypair> {
+        if let Some(encoded) = encoded {
+            let bytes = bs58::decode(encoded.trim())
+                .into_vec()
+                .ok()
+                .context(DecodeKeypairSnafu { source_name: format!("${}", self.env) })?;
+            return keypair_from_bytes(&bytes, format!("${}", self.env));
+        }
+
+        let path = self.path.as_ref().context(MissingKeypairSnafu { env: self.env.clone() })?;
+        let content = fs::read_to_string(path).context(ReadKeypairFileSnafu { path })?;
+        let source_name = path.display().to_string();
+        let bytes: Vec<u8> = serde_json::from_str(&content)
+            .ok()
+            .context(DecodeKeypairSnafu { source_name: source_name.clone() })?;
+        keypair_from_bytes(&bytes, source_name)
+    }
+}
+
+fn keypair_from_bytes(bytes: &[u8], source_name: String) -> Result<Keypair> {
+    Keypair::from_bytes(bytes).ok().context(DecodeKeypairSnafu { source_name })
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_client::solana_sdk::signer::Signer;
+
+    use super::*;
+    use crate::Error;
+
+    fn keypair_file(content: &str) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_load_keypair_from_solana_cli_file() {
+        let keypair = Keypair::new();
+        let file = keypair_file(&serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap());
+        let config =
+            KeypairConfig { env: "HERACLES_KEYPAIR".to_string(), path: Some(file.path().into()) };
+
+        let loaded = config.load_with_env(None).unwrap();
+
+        assert_eq!(loaded.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_load_keypair_from_env() {
+        let keypair = Keypair::new();
+        let config = KeypairConfig { env: "HERACLES_KEYPAIR".to_string(), path: None };
+
+        let loaded = config.load_with_env(Some(keypair.to_base58_string())).unwrap();
+
+        assert_eq!(loaded.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_keypair_errors_do_not_leak_secret() {
+        // A valid secret key followed by a mismatching public key
+        let mut bytes = Keypair::new().to_bytes().to_vec();
+        bytes[63] ^= 1;
+        let content = serde_json::to_string(&bytes).unwrap();
+        let file = keypair_file(&content);
+        let config =
+            KeypairConfig { env: "HERACLES_KEYPAIR".to_string(), path: Some(file.path().into()) };
+
+        let err = config.load_with_env(None).unwrap_err();
+
+        assert!(matches!(err, Error::DecodeKeypair { .. }));
+        let message = format!("{err} {err:?}");
+        assert!(!message.contains(&content[1..20]), "{message}");
+    }
+}
@@ -0,0 +1,656 @@
+mod config;
+mod keypair;
+mod simulate;
+
+use std::{
+    fmt, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use pump_amm::{
+    instruction::PoolAccounts,
+    order::{BuyOrder, SellOrder},
+    ProgramError,
+};
+use snafu::{ResultExt, Snafu};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use tracing::{debug, info, warn};
+
+pub use self::{config::Config, keypair::KeypairConfig, simulate::Simulation};
+
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("No keypair found, set ${} or the keypair file path", env))]
+    MissingKeypair { env: String },
+
+    #[snafu(display("Failed to read keypair file {}: {}", path.display(), source))]
+    ReadKeypairFile { path: PathBuf, source: io::Error },
+
+    #[snafu(display("Invalid keypair in {}", source_name))]
+    DecodeKeypair { source_name: String },
+
+    #[snafu(display("Failed to fetch the latest blockhash: {}", source))]
+    FetchBlockhash {
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display("Failed to simulate transaction: {}", source))]
+    SimulateTransaction {
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display("Failed to send transaction: {}", source))]
+    SendTransaction {
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display("Failed to confirm transaction {}: {}", signature, source))]
+    ConfirmTransaction {
+        signature: Signature,
+        #[snafu(source(from(ClientError, Box::new)))]
+        source: Box<ClientError>,
+    },
+
+    #[snafu(display(
+        "Transaction {} failed: {}",
+        signature,
+        program_error.map_or_else(|| err.to_string(), |program_error| program_error.to_string())
+    ))]
+    TransactionFailed {
+        signature: Signature,
+        err: TransactionError,
+        /// `pump_amm` error matching the custom code of the failed instruction
+        program_error: Option<ProgramError>,
+    },
+
+    #[snafu(display("Blockhash expired before confirmation after {} attempts", attempts))]
+    BlockhashExpired { attempts: u32 },
+
+    #[snafu(display("Transaction {} not confirmed within {:?}", signature, timeout))]
+    ConfirmationTimeout { signature: Signature, timeout: Duration },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Signs, submits and confirms transactions on behalf of a keypair
+pub struct Trader {
+    client: pump_amm::Client,
+    keypair: Keypair,
+    config: Config,
+}
+
+/// Outcome of a trade, depending on whether the trader simulates or sends transactions
+#[derive(Clone, Debug)]
+pub enum Execution {
+    Confirmed(Signature),
+    Simulated(Simulation),
+}
+
+/// Outcome of waiting for a submitted transaction
+enum Confirmation {
+    Confirmed,
+    Expired,
+}
+
+impl Trader {
+    /// Load the configured keypair and connect to the configured RPC endpoint
+    ///
+    /// # Errors
+    /// Returns an error if the keypair cannot be loaded
+    pub fn new(config: Config) -> Result<Self> {
+        let keypair = config.keypair.load()?;
+        let rpc =
+            RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+        Ok(Self::with_rpc_client(rpc, keypair, config))
+    }
+
+    #[must_use]
+    pub const fn with_rpc_client(rpc: RpcClient, keypair: Keypair, config: Config) -> Self {
+        Self { client: pump_amm::Client::new(rpc), keypair, config }
+    }
+
+    #[must_use]
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    #[must_use]
+    pub const fn rpc(&self) -> &RpcClient {
+        self.client.rpc()
+    }
+
+    /// `pump_amm` client sharing the trader's RPC endpoint, to fetch pools before trading
+    #[must_use]
+    pub const fn client(&self) -> &pump_amm::Client {
+        &self.client
+    }
+
+    /// Prepend the configured compute budget instructions to `instructions`
+    #[must_use]
+    pub fn with_compute_budget(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut all = Vec::with_capacity(instructions.len() + 2);
+        all.push(ComputeBudgetInstruction::set_compute_unit_limit(self.config.compute_unit_limit));
+        if self.config.priority_fee_micro_lamports > 0 {
+            all.push(ComputeBudgetInstruction::set_compute_unit_price(
+                self.config.priority_fee_micro_lamports,
+            ));
+        }
+        all.extend_from_slice(instructions);
+        all
+    }
+
+    /// Sign `instructions` with the latest blockhash, paid by the trader's keypair
+    ///
+    /// # Errors
+    /// Returns an error if the latest blockhash cannot be fetched
+    pub async fn transaction(&self, instructions: &[Instruction]) -> Result<(Transaction, u64)> {
+        let (blockhash, last_valid_block_height) = self
+            .rpc()
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .context(FetchBlockhashSnafu)?;
+        let transaction = Transaction::new_signed_with_payer(
+            &self.with_compute_budget(instructions),
+            Some(&self.keypair.pubkey()),
+            &[&self.keypair],
+            blockhash,
+        );
+        Ok((transaction, last_valid_block_height))
+    }
+
+    /// Submit `instructions` and wait for their confirmation
+    ///
+    /// The transaction is re-signed with a fresh blockhash and resubmitted, up to
+    /// `max_retries` times, whenever its blockhash expires before it is confirmed. A transaction
+    /// the node rejects in preflight fails like one that failed on-chain.
+    ///
+    /// # Errors
+    /// Returns an error if the transaction cannot be sent, fails, is not confirmed within
+    /// `confirm_timeout_ms` or keeps expiring
+    pub async fn submit(&self, instructions: &[Instruction]) -> Result<Signature> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let (transaction, last_valid_block_height) = self.transaction(instructions).await?;
+            let signature = self
+                .rpc()
+                .send_transaction_with_config(
+                    &transaction,
+                    RpcSendTransactionConfig {
+                        preflight_commitment: Some(CommitmentConfig::confirmed().commitment),
+                        ..RpcSendTransactionConfig::default()
+                    },
+                )
+                .await
+                .map_err(|source| send_error(transaction.signatures[0], source))?;
+            info!("Submitted transaction {signature} (attempt {attempts})");
+
+            match self.confirm(&signature, last_valid_block_height).await? {
+                Confirmation::Confirmed => {
+                    info!("Confirmed transaction {signature}");
+                    return Ok(signature);
+                }
+                Confirmation::Expired if attempts <= self.config.max_retries => {
+                    warn!("Blockhash of transaction {signature} expired, resubmitting");
+                }
+                Confirmation::Expired => return BlockhashExpiredSnafu { attempts }.fail(),
+            }
+        }
+    }
+
+    /// Run `instructions` through `simulateTransaction` without sending them
+    ///
+    /// A transaction rejected by the program is reported through [`Simulation::err`], not as an
+    /// error.
+    ///
+    /// # Errors
+    /// Returns an error if the latest blockhash cannot be fetched or the simulation request fails
+    pub async fn simulate(&self, instructions: &[Instruction]) -> Result<Simulation> {
+        let (transaction, _) = self.transaction(instructions).await?;
+        let response = self
+            .rpc()
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: true,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )
+            .await
+            .context(SimulateTransactionSnafu)?;
+        let simulation = Simulation::new(transaction.signatures[0].to_string(), response);
+        match (&simulation.err, &simulation.program_error) {
+            (None, _) => info!(
+                "Simulated transaction succeeded with {} events, {:?} compute units",
+                simulation.events.len(),
+                simulation.units_consumed
+            ),
+            (Some(err), Some(program_error)) => {
+                warn!("Simulated transaction failed with {program_error}: {err}");
+            }
+            (Some(err), None) => warn!("Simulated transaction failed: {err}"),
+        }
+        Ok(simulation)
+    }
+
+    /// Simulate or submit `instructions`, depending on the `simulate` setting
+    ///
+    /// # Errors
+    /// See [`Trader::simulate`] and [`Trader::submit`]
+    pub async fn execute(&self, instructions: &[Instruction]) -> Result<Execution> {
+        if self.config.simulate {
+            Ok(Execution::Simulated(self.simulate(instructions).await?))
+        } else {
+            Ok(Execution::Confirmed(self.submit(instructions).await?))
+        }
+    }
+
+    /// Execute a buy of `order` against the pool, after creating the user's token accounts and
+    /// wrapping the SOL spent if needed
+    ///
+    /// # Errors
+    /// See [`Trader::execute`]
+    pub async fn buy(
+        &self,
+        pool: &PoolAccounts,
+        protocol_fee_recipient: &Pubkey,
+        order: &BuyOrder,
+    ) -> Result<Execution> {
+        let user = self.pubkey();
+        let mut instructions = pool.buy_setup_ixs(&user, order.max_quote_amount_in);
+        instructions.push(pool.buy_ix(
+            &user,
+            protocol_fee_recipient,
+            order.base_amount_out,
+            order.max_quote_amount_in,
+        ));
+        self.execute(&instructions).await
+    }
+
+    /// Execute a sell of `order` against the pool, after creating the user's quote token account
+    ///
+    /// # Errors
+    /// See [`Trader::execute`]
+    pub async fn sell(
+        &self,
+        pool: &PoolAccounts,
+        protocol_fee_recipient: &Pubkey,
+        order: &SellOrder,
+    ) -> Result<Execution> {
+        let user = self.pubkey();
+        let mut instructions = pool.sell_setup_ixs(&user);
+        instructions.push(pool.sell_ix(
+            &user,
+            protocol_fee_recipient,
+            order.base_amount_in,
+            order.min_quote_amount_out,
+        ));
+        self.execute(&instructions).await
+    }
+
+    async fn confirm(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+    ) -> Result<Confirmation> {
+        let timeout = Duration::from_millis(self.config.confirm_timeout_ms);
+        let started = Instant::now();
+        loop {
+            let status = self
+                .rpc()
+                .get_signature_statuses(&[*signature])
+                .await
+                .context(ConfirmTransactionSnafu { signature: *signature })?
+                .value
+                .into_iter()
+                .next()
+                .flatten();
+            if let Some(status) = status {
+                if let Some(err) = status.err {
+                    let program_error = ProgramError::from_transaction_error(&err);
+                    return TransactionFailedSnafu { signature: *signature, err, program_error }
+                        .fail();
+                }
+                if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                    return Ok(Confirmation::Confirmed);
+                }
+            } else {
+                let block_height = self
+                    .rpc()
+                    .get_block_height()
+                    .await
+                    .context(ConfirmTransactionSnafu { signature: *signature })?;
+                if block_height > last_valid_block_height {
+                    return Ok(Confirmation::Expired);
+                }
+            }
+
+            if started.elapsed() >= timeout {
+                return ConfirmationTimeoutSnafu { signature: *signature, timeout }.fail();
+            }
+            debug!("Waiting for confirmation of transaction {signature}");
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL.min(timeout)).await;
+        }
+    }
+}
+
+/// Error of a `sendTransaction` request, naming the failure of a transaction rejected in
+/// preflight from its simulation
+fn send_error(signature: Signature, source: ClientError) -> Error {
+    if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        data: RpcResponseErrorData::SendTransactionPreflightFailure(simulation),
+        ..
+    }) = source.kind()
+    {
+        if let Some(err) = &simulation.err {
+            let program_error = simulation
+                .logs
+                .as_deref()
+                .and_then(ProgramError::from_logs)
+                .or_else(|| ProgramError::from_transaction_error(err));
+            return Error::TransactionFailed { signature, err: err.clone(), program_error };
+        }
+    }
+    Error::SendTransaction { source: Box::new(source) }
+}
+
+impl fmt::Debug for Trader {
+    // The keypair's own `Debug` implementation prints the secret key, and the query string of
+    // the RPC endpoint may hold an API key
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let url = self.rpc().url();
+        let endpoint = url.split_once('?').map_or(url.as_str(), |(endpoint, _)| endpoint);
+        f.debug_struct("Trader")
+            .field("pubkey", &self.keypair.pubkey())
+            .field("rpc_url", &endpoint)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anchor_client::solana_sdk::hash::Hash;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use pump_amm::testing::{with_context, MockRpc};
+    use serde_json::{json, Value};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_rpc_client::mock_sender::Mocks;
+
+    use super::*;
+
+    fn trader(url: &str, mocks: Mocks, config: Config) -> Trader {
+        Trader::with_rpc_client(
+            RpcClient::new_mock_with_mocks(url.to_string(), mocks),
+            Keypair::new(),
+            config,
+        )
+    }
+
+    fn memo() -> Instruction {
+        Instruction::new_with_bytes(Pubkey::new_unique(), b"heracles", vec![])
+    }
+
+    #[test]
+    fn test_compute_budget_is_prepended() {
+        let config = Config { priority_fee_micro_lamports: 10_000, ..Config::default() };
+        let trader = trader("succeeds", Mocks::default(), config);
+
+        let instructions = trader.with_compute_budget(&[memo()]);
+
+        assert_eq!(
+            instructions[..2],
+            [
+                ComputeBudgetInstruction::set_compute_unit_limit(
+                    Config::default_compute_unit_limit()
+                ),
+                ComputeBudgetInstruction::set_compute_unit_price(10_000),
+            ]
+        );
+        assert_eq!(instructions.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_submit_confirms_transaction() {
+        let trader = trader("succeeds", Mocks::default(), Config::default());
+
+        let signature = trader.submit(&[memo()]).await.unwrap();
+
+        assert_ne!(signature, Signature::default());
+    }
+
+    #[tokio::test]
+    async fn test_submit_reports_failed_transaction() {
+        let trader = trader("instruction_error", Mocks::default(), Config::default());
+
+        let error = trader.submit(&[memo()]).await.unwrap_err();
+
+        assert!(matches!(error, Error::TransactionFailed { program_error: None, .. }), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_submit_names_rejected_order() {
+        let err = json!({ "InstructionError": [2, { "Custom": 6004 }] });
+        let mocks = Mocks::from([(
+            RpcRequest::GetSignatureStatuses,
+            json!({
+                "context": { "slot": 1 },
+                "value": [{
+                    "slot": 1,
+                    "confirmations": null,
+                    "err": err,
+                    "status": { "Err": err },
+                    "confirmationStatus": "confirmed",
+                }],
+            }),
+        )]);
+        let trader = trader("succeeds", mocks, Config::default());
+
+        let error = trader.submit(&[memo()]).await.unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                Error::TransactionFailed {
+                    program_error: Some(ProgramError::ExceededSlippage),
+                    ..
+                }
+            ),
+            "{error}"
+        );
+        assert!(error.to_string().contains("ExceededSlippage"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_submit_gives_up_once_blockhash_keeps_expiring() {
+        // The mock blockhash is valid until block height 1234
+        let mocks = Mocks::from([(RpcRequest::GetBlockHeight, json!(2000))]);
+        let trader = trader("sig_not_found", mocks, Config { max_retries: 0, ..Config::default() });
+
+        let error = trader.submit(&[memo()]).await.unwrap_err();
+
+        assert!(matches!(error, Error::BlockhashExpired { attempts: 1 }), "{error}");
+    }
+
+    /// Signature of a transaction as sent over the wire, base64 encoded
+    fn sent_signature(params: &Value) -> String {
+        let wire = STANDARD.decode(params[0].as_str().unwrap()).unwrap();
+        // The signature count is a single byte for fewer than 128 signatures
+        Signature::try_from(&wire[1..65]).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_submit_resubmits_after_blockhash_expiry_over_http() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let server = MockRpc::start({
+            let sent = Arc::clone(&sent);
+            move |method, params| {
+                match method {
+                    // Each blockhash is valid for 100 blocks past the previous one
+                    "getLatestBlockhash" => {
+                        let fetched = u8::try_from(sent.lock().unwrap().len() + 1).unwrap();
+                        Ok(with_context(
+                            1,
+                            json!({
+                                "blockhash": Hash::new_from_array([fetched; 32]).to_string(),
+                                "lastValidBlockHeight": 100 * u64::from(fetched),
+                            }),
+                        ))
+                    }
+                    "sendTransaction" => {
+                        let signature = sent_signature(params);
+                        sent.lock().unwrap().push(signature.clone());
+                        Ok(json!(signature))
+                    }
+                    // The first transaction never lands, the second one does
+                    "getSignatureStatuses" => {
+                        let landed = params[0][0] == json!(sent.lock().unwrap().get(1));
+                        let status = landed.then(|| {
+                            json!({
+                                "slot": 1,
+                                "confirmations": null,
+                                "err": null,
+                                "status": { "Ok": null },
+                                "confirmationStatus": "confirmed",
+                            })
+                        });
+                        Ok(with_context(1, json!([status])))
+                    }
+                    "getBlockHeight" => Ok(json!(150)),
+                    method => {
+                        Err(json!({ "code": -32_601, "message": format!("{method} not found") }))
+                    }
+                }
+            }
+        })
+        .await;
+        let config = Config { max_retries: 1, ..Config::default() };
+        let trader = Trader::with_rpc_client(server.client(), Keypair::new(), config);
+
+        let signature = trader.submit(&[memo()]).await.unwrap();
+
+        let sent = sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert_ne!(sent[0], sent[1]);
+        assert_eq!(signature.to_string(), sent[1]);
+        assert_eq!(
+            server.methods(),
+            [
+                "getLatestBlockhash",
+                "sendTransaction",
+                "getSignatureStatuses",
+                "getBlockHeight",
+                "getLatestBlockhash",
+                "sendTransaction",
+                "getSignatureStatuses",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_submit_names_order_rejected_in_preflight() {
+        let server = MockRpc::start(|method, _| match method {
+            "getLatestBlockhash" => Ok(with_context(
+                1,
+                json!({
+                    "blockhash": Hash::new_from_array([1; 32]).to_string(),
+                    "lastValidBlockHeight": 100,
+                }),
+            )),
+            "sendTransaction" => Err(json!({
+                "code": -32_002,
+                "message": "Transaction simulation failed: Error processing Instruction 1: custom program error: 0x1774",
+                "data": {
+                    "err": { "InstructionError": [1, { "Custom": 6004 }] },
+                    "logs": [
+                        "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA invoke [1]",
+                        "Program log: AnchorError occurred. Error Code: ExceededSlippage. Error Number: 6004. Error Message: Exceeds desired slippage limit.",
+                        "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA failed: custom program error: 0x1774",
+                    ],
+                    "accounts": null,
+                    "unitsConsumed": 31_215,
+                },
+            })),
+            method => Err(json!({ "code": -32_601, "message": format!("{method} not found") })),
+        })
+        .await;
+        let trader = Trader::with_rpc_client(server.client(), Keypair::new(), Config::default());
+
+        let error = trader.submit(&[memo()]).await.unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                Error::TransactionFailed {
+                    program_error: Some(ProgramError::ExceededSlippage),
+                    ..
+                }
+            ),
+            "{error}"
+        );
+        assert_eq!(server.methods(), ["getLatestBlockhash", "sendTransaction"]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_simulates_instead_of_sending() {
+        let mocks = Mocks::from([(
+            RpcRequest::SimulateTransaction,
+            json!({
+                "context": { "slot": 330_012_345 },
+                "value": {
+                    "err": { "InstructionError": [1, { "Custom": 6004 }] },
+                    "logs": [
+                        "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA failed: custom program error: 0x1774",
+                    ],
+                    "unitsConsumed": 31_215,
+                },
+            }),
+        )]);
+        // Sending would fail the test as the mock never finds the signature
+        let config = Config { simulate: true, confirm_timeout_ms: 0, ..Config::default() };
+        let trader = trader("sig_not_found", mocks, config);
+
+        let Execution::Simulated(simulation) = trader.execute(&[memo()]).await.unwrap() else {
+            panic!("trade should have been simulated");
+        };
+
+        assert_eq!(simulation.units_consumed, Some(31_215));
+        assert_eq!(simulation.program_error, Some(ProgramError::ExceededSlippage));
+    }
+
+    #[test]
+    fn test_debug_does_not_print_secrets() {
+        let trader = trader("succeeds?api-key=secret", Mocks::default(), Config::default());
+        let secret = trader.keypair.to_base58_string();
+
+        let debug = format!("{trader:?}");
+
+        assert!(debug.contains(&trader.pubkey().to_string()));
+        assert!(debug.contains("succeeds"), "{debug}");
+        assert!(!debug.contains(&secret));
+        assert!(!debug.contains("api-key"), "{debug}");
+    }
+}
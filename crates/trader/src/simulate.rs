@@ -0,0 +1,114 @@
+use anchor_client::solana_sdk::transaction::TransactionError;
+use pump_amm::{EventRecord, ProgramError};
+use serde::Serialize;
+use solana_client::rpc_response::{Response, RpcLogsResponse, RpcSimulateTransactionResult};
+
+/// Outcome of a transaction run through `simulateTransaction` instead of being sent
+#[derive(Clone, Debug, Serialize)]
+pub struct Simulation {
+    pub signature: String,
+    pub slot: u64,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+
+    /// `pump_amm` events decoded from the simulated logs
+    pub events: Vec<EventRecord>,
+
+    pub err: Option<TransactionError>,
+
+    /// `pump_amm` error that failed the transaction, such as `ExceededSlippage`
+    pub program_error: Option<ProgramError>,
+}
+
+impl Simulation {
+    #[must_use]
+    pub fn new(signature: String, response: Response<RpcSimulateTransactionResult>) -> Self {
+        let RpcSimulateTransactionResult { err, logs, units_consumed, .. } = response.value;
+        let logs = Response {
+            context: response.context,
+            value: RpcLogsResponse { signature, err, logs: logs.unwrap_or_default() },
+        };
+        let events = pump_amm::parse_logs_response(&logs, &pump_amm::ID.to_string());
+        let program_error = ProgramError::from_logs(&logs.value.logs);
+        Self {
+            signature: logs.value.signature,
+            slot: logs.context.slot,
+            units_consumed,
+            logs: logs.value.logs,
+            events,
+            err: logs.value.err,
+            program_error,
+        }
+    }
+
+    /// Whether the transaction would have succeeded
+    #[must_use]
+    pub const fn succeeded(&self) -> bool {
+        self.err.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_client::solana_sdk::instruction::InstructionError;
+    use solana_client::rpc_response::RpcResponseContext;
+
+    use super::*;
+
+    fn simulation(err: Option<TransactionError>, logs: &[&str]) -> Simulation {
+        Simulation::new(
+            "simulated".to_string(),
+            Response {
+                context: RpcResponseContext { slot: 330_012_345, api_version: None },
+                value: RpcSimulateTransactionResult {
+                    err,
+                    logs: Some(logs.iter().map(ToString::to_string).collect()),
+                    accounts: None,
+                    units_consumed: Some(58_848),
+                    return_data: None,
+                    inner_instructions: None,
+                    replacement_blockhash: None,
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn test_simulation_names_anchor_error() {
+        let simulation = simulation(
+            Some(TransactionError::InstructionError(2, InstructionError::Custom(6004))),
+            &[
+                "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA invoke [1]",
+                "Program log: Instruction: Buy",
+                "Program log: AnchorError thrown in programs/pump-amm/src/instructions/buy.rs:206. Error Code: ExceededSlippage. Error Number: 6004. Error Message: Exceeded slippage.",
+                "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA consumed 31215 of 199700 compute units",
+                "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA failed: custom program error: 0x1774",
+            ],
+        );
+
+        assert!(!simulation.succeeded());
+        assert_eq!(simulation.program_error, Some(ProgramError::ExceededSlippage));
+        assert_eq!(simulation.units_consumed, Some(58_848));
+        assert_eq!(simulation.logs.len(), 5);
+        assert!(simulation.events.is_empty());
+    }
+
+    #[test]
+    fn test_simulation_decodes_events() {
+        let simulation = simulation(
+            None,
+            &[
+                "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA invoke [1]",
+                "Program log: Instruction: Buy",
+                "Program data: Z/RSHyz1d3d1SOpnAAAAAGb4hZQHAAAAia4KGQAAAAAAAAAAAAAAAITRnPLlBQAAfIUSmXIuAACsgqb2mAAAAOyx+hgAAAAAFAAAAAAAAAAWygwAAAAAAAUAAAAAAAAAhjIDAAAAAAACfAcZAAAAAIiuChkAAAAAs2KoY0K+8wW11Mzg7EPJF5YwXehmJXMsVZt7MEYcSPIU6zbh3Tj7uDLsYRbIHIH8Nxc2EsCB0aH03gYW/tnNAIr01Uaez9OsUAzDGS6qy0F7QYyFfp5dj72V3hxIbzYm0bqMzqITUWXmdMfp+z3UPNRElWs2FFc6IsqBgy9R1gFjg3MADqIssmTTSv9koEte+r+7dN3NBImXsZgVR9fREAe0ZyjFA6fIFZjsUWe5tjKg2nvc6Y8HxZZ7EO1veKHO",
+                "Program pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA success",
+            ],
+        );
+
+        assert!(simulation.succeeded());
+        assert_eq!(simulation.program_error, None);
+        assert_eq!(simulation.events.len(), 1);
+        assert_eq!(simulation.events[0].signature, "simulated");
+        assert_eq!(simulation.events[0].slot, 330_012_345);
+    }
+}
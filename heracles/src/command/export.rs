@@ -0,0 +1,49 @@
+use std::{io::Write, path::PathBuf};
+
+use clap::Args;
+use snafu::{ensure, ResultExt};
+use storage::{
+    export::{self, Format},
+    Bound, Store,
+};
+
+use crate::error::{self, Error};
+
+#[derive(Args, Clone)]
+pub struct ExportArgs {
+    #[clap(long, default_value_t = Format::Parquet, help = "Output format (parquet, csv, ndjson)")]
+    format: Format,
+
+    #[clap(
+        long,
+        help = "First slot or time to export, as a slot, an RFC 3339 time or a date (inclusive)"
+    )]
+    from: Option<Bound>,
+
+    #[clap(
+        long,
+        help = "Slot or time to stop at, as a slot, an RFC 3339 time or a date (exclusive)"
+    )]
+    to: Option<Bound>,
+
+    #[clap(
+        long,
+        short,
+        default_value = "export",
+        help = "Directory to write one `date=YYYY-MM-DD` partition per day to"
+    )]
+    output: PathBuf,
+}
+
+pub fn run(config: &storage::Config, args: &ExportArgs) -> Result<(), Error> {
+    // Opening the store would create an empty database rather than export nothing
+    ensure!(config.enabled, error::StorageDisabledSnafu);
+    let store = Store::open(config).context(error::OpenStoreSnafu)?;
+    let summary = export::export_trades(&store, args.from, args.to, args.format, &args.output)
+        .context(error::ExportSnafu)?;
+    let mut stdout = std::io::stdout().lock();
+    for file in &summary.files {
+        writeln!(stdout, "{}", file.display()).expect("Failed to write to stdout");
+    }
+    Ok(())
+}
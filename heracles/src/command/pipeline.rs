@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use listener::{
+    sink::{self, SinkConfig, Sinks},
+    EventSink,
+};
+use pump_amm::EventRecord;
+use storage::Store;
+use tracing::{debug, warn};
+
+/// Every destination of the decoded events: the configured sinks, then the event store
+#[derive(Debug)]
+pub struct Pipeline {
+    sinks: Sinks,
+    store: Option<Arc<Mutex<Store>>>,
+}
+
+impl Pipeline {
+    /// Build the configured sinks and open the event store if enabled, migrating its schema
+    ///
+    /// Channel sinks are dropped, they have no consumer outside of a library.
+    pub fn new(sinks: &[SinkConfig], storage: &storage::Config) -> Result<Self, storage::Error> {
+        let sinks: Vec<_> = sinks
+            .iter()
+            .filter(|sink| {
+                let channel = matches!(sink, SinkConfig::Channel(_));
+                if channel {
+                    warn!("Ignoring channel sink, it has no consumer outside of a library");
+                }
+                !channel
+            })
+            .cloned()
+            .collect();
+        let (sinks, _) = Sinks::new(&sinks);
+        let store =
+            if storage.enabled { Some(Arc::new(Mutex::new(Store::open(storage)?))) } else { None };
+        Ok(Self { sinks, store })
+    }
+}
+
+impl EventSink for Pipeline {
+    async fn write(&mut self, records: &[EventRecord]) -> sink::Result<()> {
+        self.sinks.write(records).await?;
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let store = Arc::clone(store);
+        let records = records.to_vec();
+        // SQLite blocks, keep it off the runtime threads
+        let inserted = tokio::task::spawn_blocking(move || {
+            store.lock().unwrap_or_else(PoisonError::into_inner).insert(&records)
+        })
+        .await
+        .map_err(|err| sink::Error::Custom { source: Box::new(err) })?
+        .map_err(|err| sink::Error::Custom { source: Box::new(err) })?;
+        debug!("Stored {inserted} new events");
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> sink::Result<()> {
+        // The store commits every write
+        self.sinks.flush().await
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Args};
+use listener::{capture::Speed, replay::Source, EventSink};
+use snafu::ResultExt;
+
+use super::pipeline::Pipeline;
+use crate::{
+    config::Config,
+    error::{self, Error},
+};
+
+#[derive(Args, Clone)]
+#[clap(group(ArgGroup::new("source").required(true)))]
+pub struct ReplayArgs {
+    #[clap(
+        long,
+        group = "source",
+        help = "File with one transaction signature per line, fetched from the RPC endpoint"
+    )]
+    signatures: Option<PathBuf>,
+
+    #[clap(
+        long,
+        group = "source",
+        help = "First slot of the transactions to fetch from the RPC endpoint"
+    )]
+    from_slot: Option<u64>,
+
+    #[clap(
+        long,
+        conflicts_with_all = ["signatures", "transactions", "capture"],
+        help = "Last slot of the transactions to fetch from the RPC endpoint, the latest by default"
+    )]
+    to_slot: Option<u64>,
+
+    #[clap(
+        long,
+        group = "source",
+        help = "File of `getTransaction` results, as a JSON array or one per line, replayed offline"
+    )]
+    transactions: Option<PathBuf>,
+
+    #[clap(
+        long,
+        group = "source",
+        help = "Capture file recorded by `subscribe --record`, replayed offline"
+    )]
+    capture: Option<PathBuf>,
+
+    #[clap(
+        long,
+        conflicts_with_all = ["signatures", "from_slot", "transactions"],
+        help = "Pace of a capture replay, such as `10x` or `max`, the recorded pace by default"
+    )]
+    speed: Option<Speed>,
+
+    #[clap(long, env = "HERACLES_RPC_URL", help = "Override the HTTP JSON-RPC endpoint")]
+    rpc_url: Option<String>,
+}
+
+impl ReplayArgs {
+    pub fn apply(&self, config: &mut listener::Config) {
+        if self.rpc_url.is_some() {
+            config.rpc_url.clone_from(&self.rpc_url);
+        }
+    }
+
+    fn source(self) -> Source {
+        match (self.signatures, self.from_slot, self.transactions, self.capture) {
+            (Some(path), ..) => Source::Signatures(path),
+            (_, Some(from), ..) => Source::Slots(from..=self.to_slot.unwrap_or(u64::MAX)),
+            (_, _, Some(path), _) => Source::Transactions(path),
+            (.., Some(path)) => Source::Capture { path, speed: self.speed.unwrap_or_default() },
+            // Enforced by the `source` group
+            (None, None, None, None) => unreachable!("No replay source given"),
+        }
+    }
+}
+
+/// Replay past transactions into the same sinks and event store as `heracles subscribe`
+pub async fn run(config: Config, args: ReplayArgs) -> Result<(), Error> {
+    let mut pipeline =
+        Pipeline::new(&config.listener.sinks, &config.storage).context(error::OpenStoreSnafu)?;
+    let result = listener::replay::replay(&config.listener, &args.source(), &mut pipeline).await;
+    // Flush even when the replay failed, so that the events it delivered are not lost
+    let flushed = pipeline.flush().await.map_err(|source| listener::Error::Sink { source });
+    result.and(flushed).context(error::ReplaySnafu)
+}
@@ -0,0 +1,92 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::Args;
+use listener::EventSink;
+use snafu::ResultExt;
+use tokio_graceful_shutdown::{
+    errors::CancelledByShutdown, FutureExt, SubsystemBuilder, SubsystemHandle, Toplevel,
+};
+
+use super::pipeline::Pipeline;
+use crate::{
+    config::Config,
+    error::{self, Error},
+};
+
+#[derive(Args, Clone)]
+pub struct SubscribeArgs {
+    #[clap(long, env = "HERACLES_CLUSTER", help = "Cluster to connect to (mainnet, devnet, localnet)")]
+    cluster: Option<listener::Cluster>,
+
+    #[clap(long, env = "HERACLES_WS_URL", help = "Override the websocket endpoint")]
+    ws_url: Option<String>,
+
+    #[clap(long, env = "HERACLES_RPC_URL", help = "Override the HTTP JSON-RPC endpoint")]
+    rpc_url: Option<String>,
+
+    #[clap(
+        long,
+        env = "HERACLES_API_KEY",
+        hide_env_values = true,
+        help = "API key appended to the endpoints as the `api-key` query parameter"
+    )]
+    api_key: Option<String>,
+
+    #[clap(
+        long,
+        help = "Append every raw notification to a capture file, replayable with `replay --capture`"
+    )]
+    record: Option<PathBuf>,
+}
+
+impl SubscribeArgs {
+    pub fn apply(self, config: &mut listener::Config) {
+        let Self { cluster, ws_url, rpc_url, api_key, record } = self;
+        if let Some(cluster) = cluster {
+            config.cluster = cluster;
+        }
+        if ws_url.is_some() {
+            config.ws_url = ws_url;
+        }
+        if rpc_url.is_some() {
+            config.rpc_url = rpc_url;
+        }
+        if api_key.is_some() {
+            config.api_key = api_key;
+        }
+        if record.is_some() {
+            config.record = record;
+        }
+    }
+}
+
+/// Run the listener until it fails or SIGINT / SIGTERM is received
+pub async fn run(config: Config) -> Result<(), Error> {
+    let shutdown_timeout = Duration::from_millis(config.shutdown.timeout_ms);
+    let pipeline = Pipeline::new(&config.listener.sinks, &config.storage)
+        .context(error::OpenStoreSnafu)?;
+    Toplevel::new(move |s| async move {
+        let _unused = s.start(SubsystemBuilder::new("listener", move |subsys| {
+            listener_subsystem(subsys, config.listener, pipeline)
+        }));
+    })
+    .catch_signals()
+    .handle_shutdown_requests(shutdown_timeout)
+    .await
+    .map_err(Error::from)
+}
+
+async fn listener_subsystem(
+    subsys: SubsystemHandle,
+    config: listener::Config,
+    mut pipeline: Pipeline,
+) -> Result<(), listener::Error> {
+    let result =
+        match listener::subscribe(&config, &mut pipeline).cancel_on_shutdown(&subsys).await {
+            Ok(result) => result,
+            Err(CancelledByShutdown) => Ok(()),
+        };
+    // Flush even when the listener failed, so that the events it delivered are not lost
+    let flushed = pipeline.flush().await.map_err(|source| listener::Error::Sink { source });
+    result.and(flushed)
+}
@@ -0,0 +1,277 @@
+use std::io::Write;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use clap::{Args, Subcommand};
+use pump_amm::order::{self, Slippage};
+use snafu::{OptionExt, ResultExt};
+use trader::{Execution, Trader};
+
+use crate::error::{self, Error};
+
+#[derive(Args, Clone)]
+pub struct TradeArgs {
+    #[clap(subcommand)]
+    side: Side,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Simulate the transaction with `simulateTransaction` instead of sending it"
+    )]
+    simulate: bool,
+}
+
+#[derive(Clone, Subcommand)]
+enum Side {
+    #[command(about = "Buy base tokens with `quote_amount_in` quote tokens")]
+    Buy {
+        #[clap(help = "Pool address")]
+        pool: Pubkey,
+
+        #[clap(help = "Quote tokens to spend, in base units")]
+        quote_amount_in: u64,
+
+        #[clap(long, default_value_t = 100, help = "Tolerated price move in basis points")]
+        slippage_bps: u64,
+    },
+
+    #[command(about = "Sell `base_amount_in` base tokens")]
+    Sell {
+        #[clap(help = "Pool address")]
+        pool: Pubkey,
+
+        #[clap(help = "Base tokens to sell, in base units")]
+        base_amount_in: u64,
+
+        #[clap(long, default_value_t = 100, help = "Tolerated price move in basis points")]
+        slippage_bps: u64,
+    },
+}
+
+impl TradeArgs {
+    pub const fn apply(&self, config: &mut trader::Config) {
+        if self.simulate {
+            config.simulate = true;
+        }
+    }
+}
+
+/// Quote the trade from the current pool reserves, then execute it
+pub async fn run(config: trader::Config, args: TradeArgs) -> Result<(), Error> {
+    let trader = Trader::new(config).context(error::TradeSnafu)?;
+    let output = trade(&trader, &args).await?;
+    std::io::stdout().write_all(output.as_bytes()).expect("Failed to write to stdout");
+    Ok(())
+}
+
+/// Execute the trade, returning the signature or the simulation to print
+async fn trade(trader: &Trader, args: &TradeArgs) -> Result<String, Error> {
+    let client = trader.client();
+    let address = match args.side {
+        Side::Buy { pool, .. } | Side::Sell { pool, .. } => pool,
+    };
+    let accounts = client.pool_accounts(&address).await.context(error::FetchPoolSnafu)?;
+    let global_config = client.global_config().await.context(error::FetchPoolSnafu)?;
+    let reserves = client.reserves(&accounts.pool).await.context(error::FetchPoolSnafu)?;
+    let protocol_fee_recipient =
+        global_config.protocol_fee_recipient().context(error::MissingProtocolFeeRecipientSnafu)?;
+
+    let execution = match args.side {
+        Side::Buy { quote_amount_in, slippage_bps, .. } => {
+            let order = order::buy_order(
+                quote_amount_in,
+                reserves,
+                global_config.fees(),
+                Slippage::from_basis_points(slippage_bps),
+            )
+            .context(error::QuoteSnafu)?;
+            trader.buy(&accounts, &protocol_fee_recipient, &order).await
+        }
+        Side::Sell { base_amount_in, slippage_bps, .. } => {
+            let order = order::sell_order(
+                base_amount_in,
+                reserves,
+                global_config.fees(),
+                Slippage::from_basis_points(slippage_bps),
+            )
+            .context(error::QuoteSnafu)?;
+            trader.sell(&accounts, &protocol_fee_recipient, &order).await
+        }
+    }
+    .context(error::TradeSnafu)?;
+
+    Ok(match execution {
+        Execution::Confirmed(signature) => format!("{signature}\n"),
+        Execution::Simulated(simulation) => {
+            serde_yaml::to_string(&simulation).expect("Failed to serialize simulation as yaml")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anchor_client::solana_sdk::{
+        compute_budget, hash::Hash, signature::Keypair, system_program, transaction::Transaction,
+    };
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use clap::Parser;
+    use pump_amm::{
+        instruction::{ASSOCIATED_TOKEN_PROGRAM_ID, NATIVE_MINT, TOKEN_PROGRAM_ID},
+        pda,
+        testing::{program_account, token_account, ui_account, with_context, MockRpc},
+        GlobalConfig, Pool,
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::command::{Cli, Commands};
+
+    /// Pool of a fresh token quoted in wrapped SOL
+    fn pool() -> Pool {
+        Pool {
+            pool_bump: 255,
+            index: 0,
+            creator: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: NATIVE_MINT,
+            lp_mint: Pubkey::new_unique(),
+            pool_base_token_account: Pubkey::new_unique(),
+            pool_quote_token_account: Pubkey::new_unique(),
+            lp_supply: 4_193_388_264_354,
+        }
+    }
+
+    /// Server of the pool at `address` and of `global_config`, recording the transactions it
+    /// simulates and refusing to send any
+    async fn pool_server(
+        address: Pubkey,
+        pool: Pool,
+        global_config: &GlobalConfig,
+        simulated: Arc<Mutex<Vec<Transaction>>>,
+    ) -> MockRpc {
+        let global_config = *global_config;
+        MockRpc::start(move |method, params| match method {
+            "getAccountInfo" if params[0] == json!(address.to_string()) => {
+                Ok(with_context(1, program_account(&pool)))
+            }
+            "getAccountInfo" if params[0] == json!(pda::global_config_address().to_string()) => {
+                Ok(with_context(1, program_account(&global_config)))
+            }
+            "getMultipleAccounts" if params[0][0] == json!(pool.base_mint.to_string()) => {
+                let mint = ui_account(&[0; 82], &TOKEN_PROGRAM_ID);
+                Ok(with_context(1, json!([mint, mint])))
+            }
+            "getMultipleAccounts" => Ok(with_context(
+                1,
+                json!([
+                    token_account(
+                        &pool.base_mint,
+                        &address,
+                        200_000_000_000_000,
+                        &TOKEN_PROGRAM_ID
+                    ),
+                    token_account(&NATIVE_MINT, &address, 80_000_000_000, &TOKEN_PROGRAM_ID),
+                ]),
+            )),
+            "getLatestBlockhash" => Ok(with_context(
+                1,
+                json!({ "blockhash": Hash::new_unique().to_string(), "lastValidBlockHeight": 100 }),
+            )),
+            "simulateTransaction" => {
+                let wire = STANDARD.decode(params[0].as_str().unwrap()).unwrap();
+                simulated.lock().unwrap().push(bincode::deserialize(&wire).unwrap());
+                Ok(with_context(
+                    330_012_345,
+                    json!({ "err": null, "logs": [], "unitsConsumed": 31_215 }),
+                ))
+            }
+            method => Err(json!({ "code": -32_601, "message": format!("{method} not found") })),
+        })
+        .await
+    }
+
+    fn global_config(protocol_fee_recipients: [Pubkey; 8]) -> GlobalConfig {
+        GlobalConfig {
+            admin: Pubkey::new_unique(),
+            lp_fee_basis_points: 20,
+            protocol_fee_basis_points: 5,
+            disable_flags: 0,
+            protocol_fee_recipients,
+        }
+    }
+
+    fn trade_args(args: &[&str]) -> TradeArgs {
+        let cli = Cli::try_parse_from(["heracles", "trade"].iter().chain(args)).unwrap();
+        let Some(Commands::Trade(args)) = cli.commands else {
+            panic!("Expected the trade command");
+        };
+        args
+    }
+
+    /// Trader of a fresh keypair on `server`, configured by `args`
+    fn trader(server: &MockRpc, args: &TradeArgs) -> Trader {
+        let mut config = trader::Config::default();
+        args.apply(&mut config);
+        Trader::with_rpc_client(server.client(), Keypair::new(), config)
+    }
+
+    #[tokio::test]
+    async fn test_trade_simulates_buy_and_sell() {
+        let address = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mut recipients = [Pubkey::default(); 8];
+        recipients[1] = recipient;
+        let simulated = Arc::new(Mutex::new(Vec::new()));
+        let server =
+            pool_server(address, pool(), &global_config(recipients), Arc::clone(&simulated)).await;
+        let associated_token_program = ASSOCIATED_TOKEN_PROGRAM_ID;
+        let buy = [
+            associated_token_program,
+            associated_token_program,
+            system_program::ID,
+            TOKEN_PROGRAM_ID,
+            pump_amm::ID,
+        ];
+        let sell = [associated_token_program, pump_amm::ID];
+
+        for (side, programs) in [("buy", buy.as_slice()), ("sell", sell.as_slice())] {
+            let args = trade_args(&["--simulate", side, &address.to_string(), "1000000"]);
+            let trader = trader(&server, &args);
+
+            let output = trade(&trader, &args).await.unwrap();
+
+            let transaction = simulated.lock().unwrap().pop().unwrap();
+            let message = &transaction.message;
+            let invoked: Vec<_> = message
+                .instructions
+                .iter()
+                .map(|ix| message.account_keys[usize::from(ix.program_id_index)])
+                .collect();
+            // Without a priority fee, only the compute unit limit is set
+            assert_eq!(invoked[0], compute_budget::ID, "{side}");
+            assert_eq!(invoked[1..], *programs, "{side}");
+            let swap = message.instructions.last().unwrap();
+            assert_eq!(message.account_keys[usize::from(swap.accounts[1])], trader.pubkey());
+            assert_eq!(message.account_keys[usize::from(swap.accounts[9])], recipient, "{side}");
+            assert!(output.contains(&transaction.signatures[0].to_string()), "{output}");
+            assert!(output.contains("units_consumed: 31215"), "{output}");
+        }
+        assert!(!server.methods().contains(&"sendTransaction".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_trade_requires_protocol_fee_recipient() {
+        let address = Pubkey::new_unique();
+        let simulated = Arc::new(Mutex::new(Vec::new()));
+        let config = global_config([Pubkey::default(); 8]);
+        let server = pool_server(address, pool(), &config, Arc::clone(&simulated)).await;
+        let args = trade_args(&["--simulate", "buy", &address.to_string(), "1000000"]);
+
+        let result = trade(&trader(&server, &args), &args).await;
+
+        assert!(matches!(result, Err(Error::MissingProtocolFeeRecipient)), "{result:?}");
+        assert!(simulated.lock().unwrap().is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 mod error;
 mod log;
+mod shutdown;
 
 use resolve_path::PathResolveExt;
 use serde::{Deserialize, Serialize};
@@ -7,12 +8,24 @@ use snafu::ResultExt;
 use std::path::{Path, PathBuf};
 
 pub use self::error::{Error, Result};
-use self::log::LogConfig;
+use self::{log::LogConfig, shutdown::ShutdownConfig};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Config {
     #[serde(default)]
     pub log: LogConfig,
+
+    #[serde(default)]
+    pub listener: listener::Config,
+
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+
+    #[serde(default)]
+    pub storage: storage::Config,
+
+    #[serde(default)]
+    pub trader: trader::Config,
 }
 
 impl Config {
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ShutdownConfig {
+    /// Time given to subsystems to finish once SIGINT / SIGTERM is received
+    #[serde(default = "ShutdownConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { timeout_ms: Self::default_timeout_ms() }
+    }
+}
+
+impl ShutdownConfig {
+    #[inline]
+    #[must_use]
+    pub const fn default_timeout_ms() -> u64 {
+        5_000
+    }
+}
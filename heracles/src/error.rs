@@ -1,4 +1,5 @@
 use snafu::Snafu;
+use tokio_graceful_shutdown::errors::{GracefulShutdownError, SubsystemError};
 
 use crate::config;
 
@@ -11,8 +12,35 @@ pub enum Error {
     #[snafu(display("Failed to initialize tokio runtime: {source}"))]
     InitializeTokioRuntime { source: tokio::io::Error },
 
+    #[snafu(display("Failed to open event store: {source}"))]
+    OpenStore { source: storage::Error },
+
+    #[snafu(display("Event store is disabled, set `storage.enabled` to export its trades"))]
+    StorageDisabled,
+
+    #[snafu(display("Failed to export trades: {source}"))]
+    Export { source: storage::Error },
+
+    #[snafu(display("Failed to replay transactions: {source}"))]
+    Replay { source: listener::Error },
+
+    #[snafu(display("Failed to fetch pool: {source}"))]
+    FetchPool { source: pump_amm::Error },
+
+    #[snafu(display("Global config sets no protocol fee recipient"))]
+    MissingProtocolFeeRecipient,
+
+    #[snafu(display("Failed to quote trade: {source}"))]
+    Quote { source: pump_amm::quote::Error },
+
+    #[snafu(display("{source}"))]
+    Trade { source: trader::Error },
+
+    #[snafu(display("{}", describe(errors)))]
+    Subsystem { errors: Box<[SubsystemError]> },
+
     #[snafu(display("Failed to shutdown tokio runtime: {source}"))]
-    ShutdownTokioRuntime { source: tokio_graceful_shutdown::errors::GracefulShutdownError },
+    ShutdownTokioRuntime { source: GracefulShutdownError },
 }
 
 impl From<config::Error> for Error {
@@ -21,12 +49,31 @@ impl From<config::Error> for Error {
     }
 }
 
-impl From<tokio_graceful_shutdown::errors::GracefulShutdownError> for Error {
-    fn from(source: tokio_graceful_shutdown::errors::GracefulShutdownError) -> Self {
-        Self::ShutdownTokioRuntime { source }
+impl From<GracefulShutdownError> for Error {
+    fn from(source: GracefulShutdownError) -> Self {
+        match source {
+            GracefulShutdownError::SubsystemsFailed(errors) => Self::Subsystem { errors },
+            source @ GracefulShutdownError::ShutdownTimeout(_) => {
+                Self::ShutdownTokioRuntime { source }
+            }
+        }
     }
 }
 
+/// One line per failed subsystem, with the error it returned
+fn describe(errors: &[SubsystemError]) -> String {
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|error| match error {
+            SubsystemError::Failed(name, failure) => {
+                format!("{name} failed: {}", failure.get_error())
+            }
+            SubsystemError::Panicked(name) => format!("{name} panicked"),
+        })
+        .collect();
+    lines.join("\n")
+}
+
 pub trait CommandError {
     fn exit_code(&self) -> exitcode::ExitCode;
 }
@@ -34,10 +81,17 @@ pub trait CommandError {
 impl CommandError for Error {
     fn exit_code(&self) -> exitcode::ExitCode {
         match self {
-            Self::Config { .. } => exitcode::CONFIG,
+            Self::Config { .. } | Self::StorageDisabled => exitcode::CONFIG,
             Self::InitializeTokioRuntime { .. } | Self::ShutdownTokioRuntime { .. } => {
                 exitcode::IOERR
             }
+            Self::OpenStore { .. } | Self::Export { .. } => exitcode::CANTCREAT,
+            Self::FetchPool { .. }
+            | Self::MissingProtocolFeeRecipient
+            | Self::Replay { .. }
+            | Self::Subsystem { .. } => exitcode::UNAVAILABLE,
+            Self::Quote { .. } => exitcode::DATAERR,
+            Self::Trade { .. } => exitcode::SOFTWARE,
         }
     }
 }